@@ -1,4 +1,13 @@
-use std::path::{Path, PathBuf};
+use std::{
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use crate::dirname::DirnameRef;
+use crate::error::{Error, Result};
+use crate::filename::FilenameRef;
+use crate::marker::AnyMarker;
+use crate::virtualpath::VirtualPath;
 
 /// An error that may occur while working with an [`AbsolutePath`].
 #[derive(Debug)]
@@ -13,9 +22,121 @@ impl std::fmt::Display for AbsolutePathError {
 impl std::error::Error for AbsolutePathError {}
 
 /// An absolute path.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct AbsolutePath(pub(crate) PathBuf);
 
+impl AbsolutePath {
+    /// Returns the current working directory.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the current directory cannot be determined.
+    pub fn current_dir() -> Result<Self> {
+        std::env::current_dir()
+            .map(Self)
+            .map_err(|source| Error::Io {
+                op: "get current directory",
+                virtual_path: None,
+                base: None,
+                resolved: None,
+                source,
+            })
+    }
+
+    /// Returns the home directory of the current user, if it can be determined and is absolute.
+    pub fn home_dir() -> Option<Self> {
+        std::env::home_dir().and_then(|home| Self::try_from(home).ok())
+    }
+
+    /// Resolves `path` against `cwd`.
+    ///
+    /// # Notes
+    ///
+    /// If `path` is already absolute, it replaces `cwd` entirely (the same as [`Path::join`]).
+    pub fn from_relative_to<P>(cwd: &AbsolutePath, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self(cwd.0.join(path))
+    }
+
+    /// Returns this path as a [`Path`].
+    pub fn as_path(&self) -> &Path {
+        self.0.as_path()
+    }
+
+    /// Returns a new absolute path with the given directory appended.
+    #[must_use]
+    pub fn join<D>(&self, dir: D) -> AbsolutePath
+    where
+        D: AsRef<DirnameRef>,
+    {
+        Self(self.0.join(dir.as_ref().as_path()))
+    }
+
+    /// Returns a new absolute path with the given file appended.
+    #[must_use]
+    pub fn join_file<F>(&self, file: F) -> AbsolutePath
+    where
+        F: AsRef<FilenameRef>,
+    {
+        Self(self.0.join(file.as_ref().as_path()))
+    }
+
+    /// Returns the path without its final component, if there is one.
+    ///
+    /// Returns `None` if the path is the root.
+    pub fn parent(&self) -> Option<AbsolutePath> {
+        self.0.parent().map(|parent| Self(parent.to_path_buf()))
+    }
+
+    /// Returns `true` if `base` is a prefix of this path.
+    ///
+    /// Only whole path components are matched.
+    pub fn starts_with<P>(&self, base: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.0.starts_with(base)
+    }
+
+    /// Converts this path into a [`VirtualPath`] with `prefix` as the base.
+    ///
+    /// The kind of entity at this path is unknown, so the result is marked with [`AnyMarker`].
+    /// Use [`into_file`](VirtualPath::into_file) when the kind is known, or
+    /// [`try_into_file`](VirtualPath::try_into_file) to check it against the filesystem.
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::{AbsolutePath, FileMarker, VirtualPath};
+    ///
+    /// let base = AbsolutePath::try_from("/home/blog").unwrap();
+    /// let post = AbsolutePath::try_from("/home/blog/posts/first.md").unwrap();
+    ///
+    /// let post: VirtualPath<FileMarker> = post.strip_prefix(&base).unwrap().into_file();
+    /// assert_eq!(post.to_path_buf(), PathBuf::from("/home/blog/posts/first.md"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if `prefix` is not a prefix of this path.
+    pub fn strip_prefix(&self, prefix: &AbsolutePath) -> Result<VirtualPath<AnyMarker>> {
+        let relative = self
+            .0
+            .strip_prefix(&prefix.0)
+            .map_err(|source| Error::StripPrefix {
+                path: self.0.clone(),
+                prefix: prefix.0.clone(),
+                source,
+            })?;
+        Ok(VirtualPath {
+            base: prefix.0.clone(),
+            path: relative.to_path_buf(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
 impl TryFrom<&str> for AbsolutePath {
     type Error = AbsolutePathError;
 
@@ -28,6 +149,20 @@ impl TryFrom<&str> for AbsolutePath {
     }
 }
 
+impl std::str::FromStr for AbsolutePath {
+    type Err = Error;
+
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::try_from`]. The returned error includes the offending input.
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::try_from(path).map_err(|source| Error::AbsolutePath {
+            input: Some(path.to_string()),
+            source,
+        })
+    }
+}
+
 impl TryFrom<PathBuf> for AbsolutePath {
     type Error = AbsolutePathError;
 
@@ -63,6 +198,12 @@ impl TryFrom<&Path> for AbsolutePath {
     }
 }
 
+impl std::fmt::Display for AbsolutePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0.display(), f)
+    }
+}
+
 impl AsRef<Path> for AbsolutePath {
     fn as_ref(&self) -> &Path {
         self.0.as_path()
@@ -86,6 +227,8 @@ impl Into<PathBuf> for &AbsolutePath {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dirname::Dirname;
+    use crate::filename::Filename;
 
     #[test]
     fn fail_to_create_absolute_path_with_empty_path() {
@@ -104,4 +247,61 @@ mod tests {
         let abs = AbsolutePath::try_from("/test");
         assert!(abs.is_ok());
     }
+
+    #[test]
+    fn current_dir_is_absolute() {
+        let cwd = AbsolutePath::current_dir().unwrap();
+        assert!(cwd.as_path().is_absolute());
+    }
+
+    #[test]
+    fn resolves_relative_path_against_cwd() {
+        let cwd = AbsolutePath::try_from("/home/blog").unwrap();
+        let abs = AbsolutePath::from_relative_to(&cwd, "source/posts");
+
+        assert_eq!(abs.as_path(), Path::new("/home/blog/source/posts"));
+    }
+
+    #[test]
+    fn joins_dir_and_file() {
+        let abs = AbsolutePath::try_from("/home")
+            .unwrap()
+            .join(Dirname::try_from("blog").unwrap())
+            .join_file(Filename::try_from("index.md").unwrap());
+
+        assert_eq!(abs.as_path(), Path::new("/home/blog/index.md"));
+    }
+
+    #[test]
+    fn returns_parent() {
+        let abs = AbsolutePath::try_from("/home/blog").unwrap();
+
+        let parent = abs.parent().unwrap();
+        assert_eq!(parent.as_path(), Path::new("/home"));
+
+        let root = parent.parent().unwrap();
+        assert!(root.parent().is_none());
+    }
+
+    #[test]
+    fn starts_with_only_matches_whole_components() {
+        let abs = AbsolutePath::try_from("/home/blog").unwrap();
+
+        assert!(abs.starts_with("/home"));
+        assert!(!abs.starts_with("/ho"));
+    }
+
+    #[test]
+    fn strips_prefix_into_virtual_path() {
+        let base = AbsolutePath::try_from("/home/blog").unwrap();
+        let abs = AbsolutePath::try_from("/home/blog/posts").unwrap();
+
+        let path = abs.strip_prefix(&base).unwrap();
+        assert_eq!(path.kind(), crate::MarkerKind::Any);
+        assert!(path.has_base());
+        assert_eq!(path.to_path_buf(), PathBuf::from("/home/blog/posts"));
+
+        let other = AbsolutePath::try_from("/srv").unwrap();
+        assert!(abs.strip_prefix(&other).is_err());
+    }
 }
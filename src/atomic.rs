@@ -0,0 +1,410 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::absolute::AbsolutePath;
+use crate::error::{Error, Result};
+use crate::marker::{DirMarker, File};
+use crate::virtualpath::VirtualPath;
+
+impl<K> VirtualPath<File<K>> {
+    /// Writes `contents` to this file so that readers see either the old or the new contents,
+    /// never a partial write.
+    ///
+    /// The contents are written to a temporary sibling, flushed to disk, and renamed over this
+    /// file. If this file already exists, its permissions are copied onto the new file. Missing
+    /// parent directories are created. Flushing the rename itself to disk is best-effort, since
+    /// the new contents are already in place by then.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// use vpath::{AbsolutePath, VirtualPath};
+    ///
+    /// let output = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let index = VirtualPath::default()
+    ///     .with_file_raw("blog/index.html")
+    ///     .with_base(&output);
+    ///
+    /// index.write_atomic("<h1>Blog</h1>").unwrap();
+    /// assert_eq!(std::fs::read_to_string(index.to_path_buf()).unwrap(), "<h1>Blog</h1>");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the temporary file cannot be written, given the permissions of
+    /// this file, or renamed. The temporary file is removed on failure.
+    pub fn write_atomic<C: AsRef<[u8]>>(&self, contents: C) -> Result<()> {
+        let target = self.to_path_buf();
+        let parent = target.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(parent).map_err(|e| Error::io("create parent of", self, e))?;
+
+        let permissions = match fs::metadata(&target) {
+            Ok(metadata) => Some(metadata.permissions()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(Error::io("read metadata of", self, e)),
+        };
+
+        let temp = temp_sibling(&target, "tmp");
+        let written = fs::File::create(&temp).and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp);
+            return Err(Error::io_path("write temporary file", &temp, e));
+        }
+
+        if let Err(e) = fs::rename(&temp, &target) {
+            let _ = fs::remove_file(&temp);
+            return Err(Error::io("replace", self, e));
+        }
+        let _ = sync_dir(parent);
+        Ok(())
+    }
+}
+
+/// A build output that is written to a staging directory and published in one step.
+///
+/// The staging directory is a hidden sibling of the target, so publishing is a rename on the
+/// same filesystem. Readers of the target never see a partially written build. See
+/// [`commit`](Self::commit) for when the target may briefly be missing. The staging directory is removed if the output is dropped without being committed.
+///
+/// ```
+/// # let dir = tempfile::tempdir().unwrap();
+/// use vpath::{AbsolutePath, StagedOutput};
+///
+/// let public = AbsolutePath::try_from(dir.path().join("public")).unwrap();
+///
+/// let staged = StagedOutput::begin(&public).unwrap();
+/// staged.root().with_file_raw("index.html").write_atomic("hello").unwrap();
+/// staged.commit().unwrap();
+///
+/// assert!(public.as_path().join("index.html").exists());
+/// ```
+#[derive(Debug)]
+pub struct StagedOutput {
+    target: AbsolutePath,
+    staging: AbsolutePath,
+    finished: bool,
+}
+
+impl StagedOutput {
+    /// Creates an empty staging directory for the output at `target`.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if `target` has no parent or the staging directory cannot be
+    /// created.
+    pub fn begin(target: &AbsolutePath) -> Result<Self> {
+        if target.as_path().file_name().is_none() {
+            return Err(Error::io_path(
+                "stage output for",
+                target.as_path(),
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, "target has no parent"),
+            ));
+        }
+        let staging = temp_sibling(target.as_path(), "staging");
+        fs::create_dir_all(&staging)
+            .map_err(|e| Error::io_path("create staging directory", &staging, e))?;
+
+        Ok(Self {
+            target: target.clone(),
+            staging: AbsolutePath(staging),
+            finished: false,
+        })
+    }
+
+    /// Returns the directory that the output is published to.
+    pub fn target(&self) -> &AbsolutePath {
+        &self.target
+    }
+
+    /// Returns the staging directory that the build should write to.
+    pub fn base(&self) -> &AbsolutePath {
+        &self.staging
+    }
+
+    /// Returns an empty virtual path based in the staging directory.
+    pub fn root(&self) -> VirtualPath<DirMarker> {
+        VirtualPath::default().into_base(&self.staging)
+    }
+
+    /// Publishes the staged output, replacing the target.
+    ///
+    /// On Linux, the staged output and the previous output are exchanged in one step, so readers
+    /// of the target always find either the previous or the new output. Elsewhere, or if the
+    /// filesystem cannot exchange directories, the previous output is moved aside before the
+    /// staged output is renamed into place, and is restored if the rename fails. A reader that
+    /// opens the target between these two renames finds it missing, but readers never see a mix
+    /// of the previous and the new output.
+    ///
+    /// Once the staged output is in place, removing the previous output and flushing the parent
+    /// directory to disk are best-effort, so they cannot fail a commit that already took effect.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the output cannot be published. The staging directory is
+    /// removed and the previous output is left in place. If the previous output was moved aside
+    /// and cannot be moved back, the error says where it was left.
+    pub fn commit(mut self) -> Result<()> {
+        self.finished = true;
+        let target = self.target.as_path();
+        let staging = self.staging.as_path();
+
+        let published = match exchange(staging, target) {
+            Ok(true) => Ok(Some(staging.to_path_buf())),
+            Ok(false) => replace(staging, target),
+            Err(e) => Err(Error::io_path("exchange staged output with", target, e)),
+        };
+        let previous = match published {
+            Ok(previous) => previous,
+            Err(e) => {
+                let _ = fs::remove_dir_all(staging);
+                return Err(e);
+            }
+        };
+
+        if let Some(previous) = previous {
+            let _ = fs::remove_dir_all(previous);
+        }
+        if let Some(parent) = target.parent() {
+            let _ = sync_dir(parent);
+        }
+        Ok(())
+    }
+
+    /// Discards the staged output, leaving the target untouched.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the staging directory cannot be removed.
+    pub fn rollback(mut self) -> Result<()> {
+        self.finished = true;
+        remove_staging(self.staging.as_path())
+    }
+}
+
+impl Drop for StagedOutput {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = remove_staging(self.staging.as_path());
+        }
+    }
+}
+
+fn remove_staging(staging: &Path) -> Result<()> {
+    match fs::remove_dir_all(staging) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(Error::io_path("remove staging directory", staging, e))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Publishes `staging` to `target` with two renames, returning where the previous output was
+/// moved, if there was one.
+fn replace(staging: &Path, target: &Path) -> Result<Option<PathBuf>> {
+    let previous = temp_sibling(target, "old");
+    let moved_aside = match fs::rename(target, &previous) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(Error::io_path("move aside previous output", target, e)),
+    };
+
+    if let Err(e) = fs::rename(staging, target) {
+        if moved_aside {
+            if let Err(restore) = fs::rename(&previous, target) {
+                let e = std::io::Error::new(
+                    e.kind(),
+                    format!(
+                        "{e}; the previous output could not be restored from {}: {restore}",
+                        previous.display()
+                    ),
+                );
+                return Err(Error::io_path("publish staged output to", target, e));
+            }
+        }
+        return Err(Error::io_path("publish staged output to", target, e));
+    }
+    Ok(moved_aside.then_some(previous))
+}
+
+/// Atomically swaps `staging` and `target`.
+///
+/// Returns `false` without changing anything if `target` does not exist or the filesystem or
+/// kernel cannot exchange the two.
+#[cfg(target_os = "linux")]
+fn exchange(staging: &Path, target: &Path) -> std::io::Result<bool> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let from = CString::new(staging.as_os_str().as_bytes())?;
+    let to = CString::new(target.as_os_str().as_bytes())?;
+    // SAFETY: both paths are NUL-terminated strings that outlive the call.
+    let code = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            from.as_ptr(),
+            libc::AT_FDCWD,
+            to.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    if code == 0 {
+        return Ok(true);
+    }
+    let e = std::io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::ENOENT | libc::EINVAL | libc::ENOSYS) => Ok(false),
+        _ => Err(e),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn exchange(_: &Path, _: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Returns a unique hidden path next to `path`.
+fn temp_sibling(path: &Path, purpose: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{purpose}-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Flushes the entries of a directory to disk so that renames within it are durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_tree;
+
+    fn output_dir() -> (tempfile::TempDir, AbsolutePath) {
+        let (dir, base) = temp_tree(&[]);
+        let public = AbsolutePath::try_from(base.as_path().join("public")).unwrap();
+        (dir, public)
+    }
+
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents_without_leftovers() {
+        let (dir, base) = temp_tree(&[]);
+        let file = VirtualPath::default()
+            .with_file_raw("index.html")
+            .with_base(&base);
+
+        file.write_atomic("one").unwrap();
+        file.write_atomic("two").unwrap();
+
+        assert_eq!(fs::read_to_string(file.to_path_buf()).unwrap(), "two");
+        assert_eq!(entries(dir.path()), vec!["index.html"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, base) = temp_tree(&[]);
+        let file = VirtualPath::default()
+            .with_file_raw("run.sh")
+            .with_base(&base);
+        file.write_atomic("one").unwrap();
+        fs::set_permissions(file.to_path_buf(), fs::Permissions::from_mode(0o750)).unwrap();
+
+        file.write_atomic("two").unwrap();
+
+        let mode = fs::metadata(file.to_path_buf())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+
+    #[test]
+    fn commit_replaces_previous_output() {
+        let (dir, public) = output_dir();
+        fs::create_dir(public.as_path()).unwrap();
+        fs::write(public.as_path().join("stale.html"), "stale").unwrap();
+
+        let staged = StagedOutput::begin(&public).unwrap();
+        let index = staged.root().with_file_raw("index.html");
+        index.write_atomic("fresh").unwrap();
+        assert!(!public.as_path().join("index.html").exists());
+
+        staged.commit().unwrap();
+        assert_eq!(entries(public.as_path()), vec!["index.html"]);
+        assert_eq!(entries(dir.path()), vec!["public"]);
+    }
+
+    #[test]
+    fn replace_falls_back_to_two_renames() {
+        let (dir, public) = output_dir();
+        fs::create_dir(public.as_path()).unwrap();
+        fs::write(public.as_path().join("stale.html"), "stale").unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir(&staging).unwrap();
+        fs::write(staging.join("index.html"), "fresh").unwrap();
+
+        let previous = replace(&staging, public.as_path()).unwrap().unwrap();
+
+        assert_eq!(entries(public.as_path()), vec!["index.html"]);
+        assert_eq!(entries(&previous), vec!["stale.html"]);
+    }
+
+    #[test]
+    fn rollback_and_drop_leave_target_untouched() {
+        let (dir, public) = output_dir();
+        fs::create_dir(public.as_path()).unwrap();
+        fs::write(public.as_path().join("index.html"), "old").unwrap();
+
+        let staged = StagedOutput::begin(&public).unwrap();
+        staged
+            .root()
+            .with_file_raw("new.html")
+            .write_atomic("new")
+            .unwrap();
+        staged.rollback().unwrap();
+
+        {
+            let staged = StagedOutput::begin(&public).unwrap();
+            staged
+                .root()
+                .with_file_raw("new.html")
+                .write_atomic("new")
+                .unwrap();
+        }
+
+        assert_eq!(entries(public.as_path()), vec!["index.html"]);
+        assert_eq!(entries(dir.path()), vec!["public"]);
+    }
+}
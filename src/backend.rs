@@ -0,0 +1,443 @@
+use std::fs;
+
+use crate::error::{Error, Result};
+use crate::marker::{AnyMarker, DirMarker, FileMarker, PathKind};
+use crate::virtualpath::VirtualPath;
+
+/// Performs filesystem operations on virtual paths.
+///
+/// Code that modifies the filesystem through a `Backend` can be previewed with [`DryRun`] and
+/// run for real with [`FsBackend`].
+///
+/// ```
+/// use vpath::{Backend, DryRun, FsBackend, Result, VirtualPath};
+///
+/// fn publish(backend: &mut dyn Backend) -> Result<()> {
+///     let index = VirtualPath::default().with_file_raw("index.html");
+///     backend.write(&index, b"<h1>Home</h1>")
+/// }
+///
+/// let mut dry_run = DryRun::new();
+/// publish(&mut dry_run).unwrap();
+/// assert_eq!(dry_run.to_string(), "write 13 bytes to index.html\n");
+/// ```
+pub trait Backend {
+    /// Creates a directory and all of its missing parents.
+    fn create_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()>;
+
+    /// Writes `contents` to a file, replacing it if it exists.
+    fn write(&mut self, file: &VirtualPath<FileMarker>, contents: &[u8]) -> Result<()>;
+
+    /// Copies the contents of `from` to `to`.
+    fn copy(&mut self, from: &VirtualPath<FileMarker>, to: &VirtualPath<FileMarker>) -> Result<()>;
+
+    /// Removes a file.
+    fn remove_file(&mut self, file: &VirtualPath<FileMarker>) -> Result<()>;
+
+    /// Removes an empty directory.
+    fn remove_dir(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()>;
+
+    /// Removes a directory and everything in it.
+    fn remove_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()>;
+
+    /// Renames a file or directory.
+    fn rename(&mut self, from: &VirtualPath<AnyMarker>, to: &VirtualPath<AnyMarker>) -> Result<()>;
+}
+
+/// A [`Backend`] that operates on the real filesystem.
+///
+/// Missing parent directories are created when writing or copying, and files are written with
+/// [`write_atomic`](VirtualPath::write_atomic).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FsBackend;
+
+impl Backend for FsBackend {
+    fn create_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        fs::create_dir_all(dir.to_path_buf()).map_err(|e| Error::io("create directory", dir, e))
+    }
+
+    fn write(&mut self, file: &VirtualPath<FileMarker>, contents: &[u8]) -> Result<()> {
+        file.write_atomic(contents)
+    }
+
+    fn copy(&mut self, from: &VirtualPath<FileMarker>, to: &VirtualPath<FileMarker>) -> Result<()> {
+        create_parent(to)?;
+        fs::copy(from.to_path_buf(), to.to_path_buf())
+            .map(|_| ())
+            .map_err(|e| Error::io("copy", from, e))
+    }
+
+    fn remove_file(&mut self, file: &VirtualPath<FileMarker>) -> Result<()> {
+        fs::remove_file(file.to_path_buf()).map_err(|e| Error::io("remove", file, e))
+    }
+
+    fn remove_dir(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        fs::remove_dir(dir.to_path_buf()).map_err(|e| Error::io("remove empty directory", dir, e))
+    }
+
+    fn remove_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        fs::remove_dir_all(dir.to_path_buf()).map_err(|e| Error::io("remove directory", dir, e))
+    }
+
+    fn rename(&mut self, from: &VirtualPath<AnyMarker>, to: &VirtualPath<AnyMarker>) -> Result<()> {
+        create_parent(to)?;
+        fs::rename(from.to_path_buf(), to.to_path_buf()).map_err(|e| Error::io("rename", from, e))
+    }
+}
+
+fn create_parent<M: PathKind>(path: &VirtualPath<M>) -> Result<()> {
+    match path.to_path_buf().parent() {
+        Some(parent) => {
+            fs::create_dir_all(parent).map_err(|e| Error::io("create parent of", path, e))
+        }
+        None => Ok(()),
+    }
+}
+
+/// A filesystem operation recorded by [`DryRun`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// A directory and its missing parents would be created
+    CreateDir {
+        /// The directory
+        dir: VirtualPath<DirMarker>,
+    },
+    /// A file would be written
+    Write {
+        /// The file
+        file: VirtualPath<FileMarker>,
+        /// The contents that would be written
+        contents: Vec<u8>,
+    },
+    /// A file would be copied
+    Copy {
+        /// The source file
+        from: VirtualPath<FileMarker>,
+        /// The destination file
+        to: VirtualPath<FileMarker>,
+    },
+    /// A file would be removed
+    RemoveFile {
+        /// The file
+        file: VirtualPath<FileMarker>,
+    },
+    /// An empty directory would be removed
+    RemoveEmptyDir {
+        /// The directory
+        dir: VirtualPath<DirMarker>,
+    },
+    /// A directory and everything in it would be removed
+    RemoveDir {
+        /// The directory
+        dir: VirtualPath<DirMarker>,
+    },
+    /// A file or directory would be renamed
+    Rename {
+        /// The original path
+        from: VirtualPath<AnyMarker>,
+        /// The new path
+        to: VirtualPath<AnyMarker>,
+    },
+}
+
+impl Operation {
+    /// Performs this operation using `backend`.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the backend fails to perform the operation.
+    pub fn apply<B: Backend + ?Sized>(&self, backend: &mut B) -> Result<()> {
+        match self {
+            Self::CreateDir { dir } => backend.create_dir_all(dir),
+            Self::Write { file, contents } => backend.write(file, contents),
+            Self::Copy { from, to } => backend.copy(from, to),
+            Self::RemoveFile { file } => backend.remove_file(file),
+            Self::RemoveEmptyDir { dir } => backend.remove_dir(dir),
+            Self::RemoveDir { dir } => backend.remove_dir_all(dir),
+            Self::Rename { from, to } => backend.rename(from, to),
+        }
+    }
+
+    /// Returns `true` if this operation removes or overwrites existing data.
+    ///
+    /// Removing a file or a directory with its contents is always destructive. Writing, copying,
+    /// or renaming is destructive only if the target currently exists, which is checked on the
+    /// filesystem, or if its existence cannot be determined. Creating a directory and removing an
+    /// empty one never are.
+    pub fn is_destructive(&self) -> bool {
+        match self {
+            Self::RemoveFile { .. } | Self::RemoveDir { .. } => true,
+            Self::Write { file: target, .. } | Self::Copy { to: target, .. } => {
+                target.try_exists().unwrap_or(true)
+            }
+            Self::Rename { to, .. } => to.try_exists().unwrap_or(true),
+            Self::CreateDir { .. } | Self::RemoveEmptyDir { .. } => false,
+        }
+    }
+}
+
+/// Writes the virtual path, followed by the resolved path if the virtual path has a base.
+fn fmt_path<M: PathKind>(
+    f: &mut std::fmt::Formatter<'_>,
+    path: &VirtualPath<M>,
+) -> std::fmt::Result {
+    write!(f, "{}", path.path.display())?;
+    if path.has_base() {
+        write!(f, " ({path})")?;
+    }
+    Ok(())
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateDir { dir } => {
+                f.write_str("create directory ")?;
+                fmt_path(f, dir)
+            }
+            Self::Write { file, contents } => {
+                write!(f, "write {} bytes to ", contents.len())?;
+                fmt_path(f, file)
+            }
+            Self::Copy { from, to } => {
+                f.write_str("copy ")?;
+                fmt_path(f, from)?;
+                f.write_str(" to ")?;
+                fmt_path(f, to)
+            }
+            Self::RemoveFile { file } => {
+                f.write_str("remove ")?;
+                fmt_path(f, file)
+            }
+            Self::RemoveEmptyDir { dir } => {
+                f.write_str("remove empty directory ")?;
+                fmt_path(f, dir)
+            }
+            Self::RemoveDir { dir } => {
+                f.write_str("remove directory ")?;
+                fmt_path(f, dir)
+            }
+            Self::Rename { from, to } => {
+                f.write_str("rename ")?;
+                fmt_path(f, from)?;
+                f.write_str(" to ")?;
+                fmt_path(f, to)
+            }
+        }
+    }
+}
+
+/// A [`Backend`] that records operations instead of performing them.
+///
+/// The recorded operations are displayed as a plan, one operation per line, and can be replayed
+/// against another backend once the plan has been reviewed.
+///
+/// ```
+/// # let dir = tempfile::tempdir().unwrap();
+/// use vpath::{AbsolutePath, Backend, DryRun, FsBackend, VirtualPath};
+///
+/// let output = AbsolutePath::try_from(dir.path()).unwrap();
+/// let root = VirtualPath::default().with_base(&output);
+///
+/// let mut dry_run = DryRun::new();
+/// dry_run.write(&root.clone().with_file_raw("a.txt"), b"a").unwrap();
+/// dry_run.remove_dir_all(&root.with_dir_raw("old")).unwrap();
+/// assert_eq!(dry_run.len(), 2);
+/// assert!(!dir.path().join("a.txt").exists());
+/// assert_eq!(
+///     dry_run.to_string(),
+///     format!(
+///         "write 1 bytes to a.txt ({0}/a.txt)\nremove directory old ({0}/old)\n",
+///         output.as_path().display()
+///     )
+/// );
+///
+/// dry_run.operations()[0].apply(&mut FsBackend).unwrap();
+/// assert!(dir.path().join("a.txt").exists());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DryRun {
+    operations: Vec<Operation>,
+}
+
+impl DryRun {
+    /// Creates a backend with no recorded operations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded operations in the order they were requested.
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Returns the recorded operations, consuming the backend.
+    pub fn into_operations(self) -> Vec<Operation> {
+        self.operations
+    }
+
+    /// Returns the number of recorded operations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns `true` if no operations have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Removes all recorded operations.
+    pub fn clear(&mut self) {
+        self.operations.clear();
+    }
+
+    /// Performs every recorded operation, in order, using `backend`.
+    ///
+    /// # Errors
+    ///
+    /// Replay stops at the first operation that fails and its error is returned. Earlier
+    /// operations are not undone.
+    pub fn replay<B: Backend + ?Sized>(&self, backend: &mut B) -> Result<()> {
+        self.operations
+            .iter()
+            .try_for_each(|operation| operation.apply(backend))
+    }
+}
+
+impl Backend for DryRun {
+    fn create_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        self.operations
+            .push(Operation::CreateDir { dir: dir.clone() });
+        Ok(())
+    }
+
+    fn write(&mut self, file: &VirtualPath<FileMarker>, contents: &[u8]) -> Result<()> {
+        self.operations.push(Operation::Write {
+            file: file.clone(),
+            contents: contents.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn copy(&mut self, from: &VirtualPath<FileMarker>, to: &VirtualPath<FileMarker>) -> Result<()> {
+        self.operations.push(Operation::Copy {
+            from: from.clone(),
+            to: to.clone(),
+        });
+        Ok(())
+    }
+
+    fn remove_file(&mut self, file: &VirtualPath<FileMarker>) -> Result<()> {
+        self.operations
+            .push(Operation::RemoveFile { file: file.clone() });
+        Ok(())
+    }
+
+    fn remove_dir(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        self.operations
+            .push(Operation::RemoveEmptyDir { dir: dir.clone() });
+        Ok(())
+    }
+
+    fn remove_dir_all(&mut self, dir: &VirtualPath<DirMarker>) -> Result<()> {
+        self.operations
+            .push(Operation::RemoveDir { dir: dir.clone() });
+        Ok(())
+    }
+
+    fn rename(&mut self, from: &VirtualPath<AnyMarker>, to: &VirtualPath<AnyMarker>) -> Result<()> {
+        self.operations.push(Operation::Rename {
+            from: from.clone(),
+            to: to.clone(),
+        });
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DryRun {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for operation in &self.operations {
+            writeln!(f, "{operation}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::absolute::AbsolutePath;
+    use crate::testutil::temp_tree;
+
+    #[test]
+    fn plan_shows_virtual_and_resolved_paths() {
+        let output = AbsolutePath::try_from("/srv/site").unwrap();
+        let root = VirtualPath::default().with_base(&output);
+        let page = root.clone().with_file_raw("blog/index.html");
+
+        let mut dry_run = DryRun::new();
+        dry_run
+            .create_dir_all(&root.clone().with_dir_raw("blog"))
+            .unwrap();
+        dry_run
+            .rename(
+                &page.clone().into_any(),
+                &page.with_extension("htm").into_any(),
+            )
+            .unwrap();
+        dry_run.remove_dir_all(&root.with_dir_raw("old")).unwrap();
+
+        assert_eq!(
+            dry_run.to_string(),
+            "create directory blog (/srv/site/blog)\n\
+             rename blog/index.html (/srv/site/blog/index.html) \
+             to blog/index.htm (/srv/site/blog/index.htm)\n\
+             remove directory old (/srv/site/old)\n"
+        );
+        assert!(!dry_run.operations()[0].is_destructive());
+        assert!(dry_run.operations()[2].is_destructive());
+    }
+
+    #[test]
+    fn overwriting_an_existing_target_is_destructive() {
+        let (_dir, output) = temp_tree(&[("old.txt", "")]);
+        let root = VirtualPath::default().with_base(&output);
+        let old = root.clone().with_file_raw("old.txt");
+        let new = root.clone().with_file_raw("new.txt");
+
+        let mut dry_run = DryRun::new();
+        dry_run.write(&new, b"new").unwrap();
+        dry_run.write(&old, b"old").unwrap();
+        dry_run.copy(&old, &new).unwrap();
+        dry_run
+            .rename(&new.clone().into_any(), &old.clone().into_any())
+            .unwrap();
+        dry_run.remove_dir(&root.with_dir_raw("empty")).unwrap();
+
+        let destructive: Vec<_> = dry_run
+            .operations()
+            .iter()
+            .map(Operation::is_destructive)
+            .collect();
+        assert_eq!(destructive, vec![false, true, false, true, false]);
+    }
+
+    #[test]
+    fn replay_performs_recorded_operations() {
+        let (dir, output) = temp_tree(&[]);
+        let root = VirtualPath::default().with_base(&output);
+        let a = root.clone().with_file_raw("a/a.txt");
+        let b = root.clone().with_file_raw("b/b.txt");
+
+        let mut dry_run = DryRun::new();
+        dry_run.write(&a, b"hello").unwrap();
+        dry_run.copy(&a, &b).unwrap();
+        dry_run.remove_file(&a).unwrap();
+        assert!(!dir.path().join("a").exists());
+
+        dry_run.replay(&mut FsBackend).unwrap();
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(b.to_path_buf()).unwrap(), "hello");
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::absolute::AbsolutePath;
+use crate::backend::{Backend, FsBackend};
+use crate::error::{Error, Result};
+use crate::glob::Glob;
+use crate::marker::{DirMarker, FileMarker, PathKind};
+use crate::virtualpath::VirtualPath;
+use crate::virtualpathref::VirtualPathRef;
+
+/// Finds and removes files in an output directory that were not produced by a build.
+///
+/// Outputs are matched by their relative part, so they may be given with or without a base.
+/// Files matching a keep glob are never reported. Symbolic links are not followed: a link is
+/// treated as a file, so nothing outside the output directory is removed.
+///
+/// ```
+/// # let dir = tempfile::tempdir().unwrap();
+/// # std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+/// # std::fs::create_dir_all(dir.path().join("old")).unwrap();
+/// # std::fs::write(dir.path().join(".git/HEAD"), "").unwrap();
+/// # std::fs::write(dir.path().join("old/index.html"), "").unwrap();
+/// # std::fs::write(dir.path().join("index.html"), "").unwrap();
+/// use vpath::{AbsolutePath, Glob, OrphanCleanup, VirtualPath};
+///
+/// let output = AbsolutePath::try_from(dir.path()).unwrap();
+/// let built = vec![VirtualPath::default().with_file_raw("index.html")];
+///
+/// let cleanup = OrphanCleanup::new(&output).keep(Glob::new(".git/**").unwrap());
+/// let orphans = cleanup.find(&built).unwrap();
+/// assert_eq!(orphans[0].relative().as_path().to_str(), Some("old/index.html"));
+///
+/// let report = cleanup.remove(&built).unwrap();
+/// assert_eq!(report.to_string(), "removed 1 files and 1 directories");
+/// assert!(dir.path().join(".git/HEAD").exists());
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrphanCleanup {
+    root: VirtualPath<DirMarker>,
+    keep: Vec<Glob>,
+}
+
+impl OrphanCleanup {
+    /// Creates a cleanup of the `output` directory.
+    pub fn new(output: &AbsolutePath) -> Self {
+        Self {
+            root: VirtualPath::default().into_base(output),
+            keep: Vec::new(),
+        }
+    }
+
+    /// Never reports files or removes directories matching `glob`.
+    ///
+    /// Directories matching `glob` are not entered, so `.git/**` skips the whole `.git`
+    /// directory.
+    #[must_use]
+    pub fn keep(mut self, glob: Glob) -> Self {
+        self.keep.push(glob);
+        self
+    }
+
+    /// Returns the files in the output directory that are not in `outputs` and not kept.
+    ///
+    /// Files are returned with the output directory as their base.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the output directory cannot be walked.
+    pub fn find<I>(&self, outputs: I) -> Result<Vec<VirtualPath<FileMarker>>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<VirtualPathRef<FileMarker>>,
+    {
+        Ok(self.scan(outputs)?.0)
+    }
+
+    /// Removes orphaned files from the output directory, along with any directories left empty.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the output directory cannot be walked or a file or directory
+    /// cannot be removed. Files removed before the error stay removed.
+    pub fn remove<I>(&self, outputs: I) -> Result<CleanupReport>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<VirtualPathRef<FileMarker>>,
+    {
+        self.remove_with(outputs, &mut FsBackend)
+    }
+
+    /// Removes orphaned files and emptied directories through `backend`.
+    ///
+    /// A directory is removed if it is empty once the orphans are removed, including one that
+    /// was already empty or only holds such directories. Directories are removed deepest first.
+    ///
+    /// # Errors
+    ///
+    /// See [`remove`](Self::remove).
+    pub fn remove_with<I, B>(&self, outputs: I, backend: &mut B) -> Result<CleanupReport>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<VirtualPathRef<FileMarker>>,
+        B: Backend + ?Sized,
+    {
+        let (orphans, mut dirs) = self.scan(outputs)?;
+        let mut report = CleanupReport::default();
+
+        dirs.sort_by(|a, b| {
+            let depth = |path: &Path| path.components().count();
+            depth(b).cmp(&depth(a)).then_with(|| a.cmp(b))
+        });
+        let dirs: Vec<VirtualPath<DirMarker>> =
+            dirs.into_iter().map(|dir| self.root_join(dir)).collect();
+
+        for file in orphans {
+            backend.remove_file(&file)?;
+            report.files.push(file);
+        }
+        for dir in dirs {
+            match backend.remove_dir(&dir) {
+                Ok(()) => report.dirs.push(dir),
+                Err(e)
+                    if e.io_error().map(std::io::Error::kind)
+                        == Some(ErrorKind::DirectoryNotEmpty) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the orphaned files and the relative paths of directories that are empty once the
+    /// orphans are removed.
+    ///
+    /// Directories matching a keep glob are not entered.
+    fn scan<I>(&self, outputs: I) -> Result<(Vec<VirtualPath<FileMarker>>, Vec<PathBuf>)>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<VirtualPathRef<FileMarker>>,
+    {
+        let outputs: HashSet<PathBuf> = outputs
+            .into_iter()
+            .map(|output| output.as_ref().as_path().to_path_buf())
+            .collect();
+
+        let mut orphans = Vec::new();
+        let mut dirs = Vec::new();
+        if self.root.try_exists()? {
+            self.scan_dir(PathBuf::new(), &outputs, &mut orphans, &mut dirs)?;
+        }
+        Ok((orphans, dirs))
+    }
+
+    /// Scans the directory at the relative path `dir`, returning `true` if it is empty once the
+    /// orphans are removed.
+    ///
+    /// Symbolic links are not followed, so a link is treated as a file.
+    fn scan_dir(
+        &self,
+        dir: PathBuf,
+        outputs: &HashSet<PathBuf>,
+        orphans: &mut Vec<VirtualPath<FileMarker>>,
+        dirs: &mut Vec<PathBuf>,
+    ) -> Result<bool> {
+        let dir: VirtualPath<DirMarker> = self.root_join(dir);
+        let read = |e| Error::io("read directory", &dir, e);
+        let mut entries = fs::read_dir(dir.to_path_buf())
+            .and_then(|entries| {
+                entries
+                    .map(|entry| {
+                        let entry = entry?;
+                        Ok((entry.file_name(), entry.file_type()?))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .map_err(read)?;
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut empty = true;
+        for (name, file_type) in entries {
+            let path = dir.path.join(name);
+            if self.is_kept(&path) {
+                empty = false;
+            } else if file_type.is_dir() {
+                if self.scan_dir(path.clone(), outputs, orphans, dirs)? {
+                    dirs.push(path);
+                } else {
+                    empty = false;
+                }
+            } else if outputs.contains(&path) {
+                empty = false;
+            } else {
+                orphans.push(self.root_join(path));
+            }
+        }
+        Ok(empty)
+    }
+
+    /// Returns the path relative to the output directory as a virtual path.
+    fn root_join<M: PathKind>(&self, path: PathBuf) -> VirtualPath<M> {
+        VirtualPath {
+            base: self.root.base.clone(),
+            path,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn is_kept(&self, path: &Path) -> bool {
+        self.keep.iter().any(|glob| glob.is_match(path))
+    }
+}
+
+/// The files and directories removed by an [`OrphanCleanup`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    files: Vec<VirtualPath<FileMarker>>,
+    dirs: Vec<VirtualPath<DirMarker>>,
+}
+
+impl CleanupReport {
+    /// Returns the removed files.
+    pub fn files(&self) -> &[VirtualPath<FileMarker>] {
+        &self.files
+    }
+
+    /// Returns the removed directories, deepest first.
+    pub fn dirs(&self) -> &[VirtualPath<DirMarker>] {
+        &self.dirs
+    }
+
+    /// Returns `true` if nothing was removed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.dirs.is_empty()
+    }
+}
+
+impl std::fmt::Display for CleanupReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "removed {} files and {} directories",
+            self.files.len(),
+            self.dirs.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::DryRun;
+    use crate::testutil::{names, temp_tree};
+
+    #[test]
+    fn removes_orphans_and_emptied_directories() {
+        let (dir, output) = temp_tree(&[
+            ("index.html", ""),
+            ("blog/new.html", ""),
+            ("blog/old.html", ""),
+            ("old/a/b.html", ""),
+            ("keep/notes.txt", ""),
+        ]);
+        let built = [
+            VirtualPath::default().with_file_raw("index.html"),
+            VirtualPath::default().with_file_raw("blog/new.html"),
+        ];
+
+        let report = OrphanCleanup::new(&output)
+            .keep(Glob::new("keep/**").unwrap())
+            .remove(&built)
+            .unwrap();
+
+        assert_eq!(names(report.files()), vec!["blog/old.html", "old/a/b.html"]);
+        assert_eq!(names(report.dirs()), vec!["old/a", "old"]);
+        assert!(dir.path().join("blog/new.html").exists());
+        assert!(dir.path().join("keep/notes.txt").exists());
+        assert!(!dir.path().join("old").exists());
+    }
+
+    #[test]
+    fn removes_empty_directories_and_skips_kept_ones() {
+        let (dir, output) = temp_tree(&[
+            ("index.html", ""),
+            ("empty/", ""),
+            ("nested/a/b/", ""),
+            ("nested/c/", ""),
+            (".git/refs/", ""),
+        ]);
+        let built = [VirtualPath::default().with_file_raw("index.html")];
+
+        let report = OrphanCleanup::new(&output)
+            .keep(Glob::new(".git/**").unwrap())
+            .remove(&built)
+            .unwrap();
+
+        assert!(report.files().is_empty());
+        assert_eq!(
+            names(report.dirs()),
+            vec!["nested/a/b", "nested/a", "nested/c", "empty", "nested"]
+        );
+        assert!(dir.path().join(".git/refs").exists());
+        assert!(dir.path().join("index.html").exists());
+    }
+
+    #[test]
+    fn dry_run_leaves_output_untouched() {
+        let (dir, output) = temp_tree(&[("stale/index.html", "")]);
+
+        let mut dry_run = DryRun::new();
+        let report = OrphanCleanup::new(&output)
+            .remove_with(Vec::<VirtualPath<FileMarker>>::new(), &mut dry_run)
+            .unwrap();
+
+        assert_eq!(report.to_string(), "removed 1 files and 1 directories");
+        assert_eq!(
+            dry_run.to_string(),
+            format!(
+                "remove stale/index.html ({0}/stale/index.html)\n\
+                 remove empty directory stale ({0}/stale)\n",
+                dir.path().display()
+            )
+        );
+        assert!(dir.path().join("stale/index.html").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_links_out_of_the_output() {
+        let (outside, _) = temp_tree(&[("precious.txt", "")]);
+        let (dir, output) = temp_tree(&[]);
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+        let report = OrphanCleanup::new(&output)
+            .remove(Vec::<VirtualPath<FileMarker>>::new())
+            .unwrap();
+
+        assert_eq!(names(report.files()), vec!["link"]);
+        assert!(outside.path().join("precious.txt").exists());
+    }
+}
@@ -0,0 +1,33 @@
+use std::ffi::OsStr;
+use std::path::{self, Path};
+
+use crate::dirname::Dirname;
+use crate::error::{Error, Result};
+use crate::filename::Filename;
+
+/// A single component of a [`VirtualPath`](crate::VirtualPath).
+#[derive(Debug, Clone)]
+pub enum Component {
+    /// A directory
+    Dir(Dirname),
+    /// The final file of a file path
+    File(Filename),
+}
+
+/// Returns the names of the components of a relative path, skipping `.` components.
+///
+/// # Errors
+///
+/// An [`Error::NotRelative`] will be returned if the path has a root, a prefix, or a `..`
+/// component.
+pub(crate) fn relative_names(path: &Path) -> Result<Vec<&OsStr>> {
+    path.components()
+        .filter(|component| !matches!(component, path::Component::CurDir))
+        .map(|component| match component {
+            path::Component::Normal(name) => Ok(name),
+            _ => Err(Error::NotRelative {
+                path: path.to_path_buf(),
+            }),
+        })
+        .collect()
+}
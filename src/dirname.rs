@@ -1,14 +1,27 @@
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Borrow,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
 /// A directory component for a [`VirtualPath`](crate::VirtualPath).
 ///
 /// Directory components consist of a directory possibly containing subdirectores. No absolute
 /// paths are allowed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Dirname {
     pub(crate) name: PathBuf,
 }
 
+/// A borrowed [`Dirname`].
+///
+/// This is to [`Dirname`] what [`Path`] is to [`PathBuf`].
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct DirnameRef {
+    name: Path,
+}
+
 /// An error that may occur when constructing a [`Dirname`].
 #[derive(Debug)]
 pub enum DirnameError {
@@ -45,6 +58,20 @@ impl TryFrom<&str> for Dirname {
     }
 }
 
+impl std::str::FromStr for Dirname {
+    type Err = crate::Error;
+
+    /// # Errors
+    ///
+    /// See [`Dirname::try_from`]. The returned error includes the offending input.
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::try_from(path).map_err(|source| crate::Error::Dirname {
+            input: Some(path.to_string()),
+            source,
+        })
+    }
+}
+
 impl TryFrom<PathBuf> for Dirname {
     type Error = DirnameError;
 
@@ -74,16 +101,93 @@ impl TryFrom<&Path> for Dirname {
     ///
     /// An `Err` will be returned if the dirname is an absolute path or if no path was provided.
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        DirnameRef::new(path).map(ToOwned::to_owned)
+    }
+}
+
+impl DirnameRef {
+    /// Borrows `path` as a dirname.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the dirname is an absolute path or if no path was provided.
+    pub fn new<P>(path: &P) -> Result<&DirnameRef, DirnameError>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let path = path.as_ref();
         if path.components().next().is_none() {
             return Err(DirnameError::Empty);
         }
 
         (!path.is_absolute())
-            .then_some(Dirname {
-                name: path.to_path_buf(),
-            })
+            .then(|| Self::from_path_unchecked(path))
             .ok_or(DirnameError::Absolute)
     }
+
+    pub(crate) fn from_path_unchecked(path: &Path) -> &DirnameRef {
+        // SAFETY: `DirnameRef` is `repr(transparent)` over `Path`.
+        unsafe { &*(path as *const Path as *const DirnameRef) }
+    }
+
+    /// Returns this dirname as a [`Path`].
+    pub fn as_path(&self) -> &Path {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for Dirname {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.name.display(), f)
+    }
+}
+
+impl std::fmt::Display for DirnameRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.name.display(), f)
+    }
+}
+
+impl Deref for Dirname {
+    type Target = DirnameRef;
+
+    fn deref(&self) -> &DirnameRef {
+        DirnameRef::from_path_unchecked(&self.name)
+    }
+}
+
+impl Borrow<DirnameRef> for Dirname {
+    fn borrow(&self) -> &DirnameRef {
+        self
+    }
+}
+
+impl ToOwned for DirnameRef {
+    type Owned = Dirname;
+
+    fn to_owned(&self) -> Dirname {
+        Dirname {
+            name: self.name.to_path_buf(),
+        }
+    }
+}
+
+impl AsRef<DirnameRef> for Dirname {
+    fn as_ref(&self) -> &DirnameRef {
+        self
+    }
+}
+
+impl AsRef<DirnameRef> for DirnameRef {
+    fn as_ref(&self) -> &DirnameRef {
+        self
+    }
+}
+
+impl AsRef<Path> for DirnameRef {
+    fn as_ref(&self) -> &Path {
+        &self.name
+    }
 }
 
 #[cfg(test)]
@@ -101,4 +205,30 @@ mod tests {
         let dir = Dirname::try_from("/test");
         assert!(dir.is_err());
     }
+
+    #[test]
+    fn borrows_dirname() {
+        let dir = DirnameRef::new("data/posts").unwrap();
+        assert_eq!(dir.as_path(), Path::new("data/posts"));
+
+        let owned: Dirname = dir.to_owned();
+        assert_eq!(owned.as_path(), Path::new("data/posts"));
+    }
+
+    #[test]
+    fn displays_and_orders_dirnames() {
+        let mut dirs = std::collections::BTreeSet::new();
+        dirs.insert(Dirname::try_from("posts").unwrap());
+        dirs.insert(Dirname::try_from("assets").unwrap());
+
+        assert!(dirs.contains(DirnameRef::new("posts").unwrap()));
+        let names: Vec<_> = dirs.iter().map(ToString::to_string).collect();
+        assert_eq!(names, ["assets", "posts"]);
+    }
+
+    #[test]
+    fn fail_to_borrow_invalid_dirname() {
+        assert!(DirnameRef::new("").is_err());
+        assert!(DirnameRef::new("/test").is_err());
+    }
 }
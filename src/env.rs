@@ -0,0 +1,201 @@
+use std::{collections::HashMap, ffi::OsString, path::PathBuf};
+
+/// A source of environment variables and home directories.
+///
+/// This is used when constructing paths that depend on the environment so that the environment
+/// can be replaced in tests.
+pub trait Environment {
+    /// Returns the value of the environment variable `name`, if it is set.
+    fn var(&self, name: &str) -> Option<OsString>;
+
+    /// Returns the home directory of the current user.
+    fn home_dir(&self) -> Option<PathBuf>;
+
+    /// Returns the home directory of the given user.
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf>;
+}
+
+/// The environment of the current process.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemEnv;
+
+impl Environment for SystemEnv {
+    fn var(&self, name: &str) -> Option<OsString> {
+        std::env::var_os(name)
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        std::env::home_dir()
+    }
+
+    /// Looks up the user with `getpwnam_r`, so users from any source configured for the system,
+    /// such as LDAP, are found.
+    #[cfg(unix)]
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf> {
+        use std::ffi::{CStr, CString, OsStr};
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = CString::new(user).ok()?;
+        let mut buf: Vec<libc::c_char> = vec![0; 1024];
+        loop {
+            // SAFETY: `passwd` is plain data, and every pointer in it is set by `getpwnam_r`
+            // before it is read.
+            let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+            let mut found = std::ptr::null_mut();
+            // SAFETY: every pointer is valid for the duration of the call, and `buf.len()` is the
+            // size of the buffer.
+            let code = unsafe {
+                libc::getpwnam_r(
+                    name.as_ptr(),
+                    &mut entry,
+                    buf.as_mut_ptr(),
+                    buf.len(),
+                    &mut found,
+                )
+            };
+            if code == libc::ERANGE && buf.len() < 1 << 20 {
+                buf.resize(buf.len() * 2, 0);
+                continue;
+            }
+            if code != 0 || found.is_null() || entry.pw_dir.is_null() {
+                return None;
+            }
+            // SAFETY: on success `pw_dir` points at a NUL-terminated string inside `buf`.
+            let home = unsafe { CStr::from_ptr(entry.pw_dir) };
+            return Some(PathBuf::from(OsStr::from_bytes(home.to_bytes())));
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn user_home_dir(&self, _user: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// An in-memory [`Environment`].
+///
+/// The home directory of the current user is read from the `HOME` variable.
+///
+/// ```
+/// use vpath::{EnvMap, Environment};
+///
+/// let env = EnvMap::new()
+///     .with_var("HOME", "/home/blog")
+///     .with_user_home("admin", "/home/admin");
+///
+/// assert_eq!(env.home_dir().unwrap().to_str(), Some("/home/blog"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EnvMap {
+    vars: HashMap<String, OsString>,
+    users: HashMap<String, PathBuf>,
+}
+
+impl EnvMap {
+    /// Creates an empty environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets an environment variable.
+    pub fn set_var<K, V>(&mut self, name: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<OsString>,
+    {
+        self.vars.insert(name.into(), value.into());
+    }
+
+    /// Returns this environment with the given variable set.
+    #[must_use]
+    pub fn with_var<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<OsString>,
+    {
+        self.set_var(name, value);
+        self
+    }
+
+    /// Returns this environment with the home directory of `user` set.
+    #[must_use]
+    pub fn with_user_home<U, P>(mut self, user: U, home: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<PathBuf>,
+    {
+        self.users.insert(user.into(), home.into());
+        self
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for EnvMap
+where
+    K: Into<String>,
+    V: Into<OsString>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut env = EnvMap::new();
+        for (name, value) in iter {
+            env.set_var(name, value);
+        }
+        env
+    }
+}
+
+impl Environment for EnvMap {
+    fn var(&self, name: &str) -> Option<OsString> {
+        self.vars.get(name).cloned()
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        self.var("HOME").map(PathBuf::from)
+    }
+
+    fn user_home_dir(&self, user: &str) -> Option<PathBuf> {
+        self.users.get(user).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn looks_up_system_users() {
+        use std::ffi::{CStr, OsStr};
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut buf: Vec<libc::c_char> = vec![0; 1 << 16];
+        // SAFETY: `passwd` is plain data, and every pointer in it is set by `getpwuid_r` before
+        // it is read.
+        let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut found = std::ptr::null_mut();
+        // SAFETY: every pointer is valid for the duration of the call, and `buf.len()` is the size
+        // of the buffer.
+        let code = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut entry,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut found,
+            )
+        };
+        // The current user may have no entry, for example in a container started with an
+        // arbitrary uid.
+        if code == 0 && !found.is_null() {
+            // SAFETY: on success `pw_name` and `pw_dir` point at NUL-terminated strings inside
+            // `buf`.
+            let (name, home) =
+                unsafe { (CStr::from_ptr(entry.pw_name), CStr::from_ptr(entry.pw_dir)) };
+            assert_eq!(
+                SystemEnv.user_home_dir(name.to_str().unwrap()),
+                Some(PathBuf::from(OsStr::from_bytes(home.to_bytes())))
+            );
+        }
+        assert_eq!(SystemEnv.user_home_dir("no-such-user-vpath"), None);
+        assert_eq!(SystemEnv.user_home_dir("bad\0name"), None);
+    }
+}
@@ -0,0 +1,421 @@
+use std::path::{Path, PathBuf, StripPrefixError};
+
+use crate::absolute::AbsolutePathError;
+use crate::dirname::DirnameError;
+use crate::expand::ExpandError;
+use crate::filename::FilenameError;
+use crate::glob::GlobError;
+use crate::marker::{MarkerKind, PathKind};
+use crate::utf8::Utf8Error;
+use crate::virtualpath::VirtualPath;
+use crate::xdg::XdgError;
+
+/// A `Result` using the crate [`Error`].
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error that may occur while working with paths in this crate.
+///
+/// Errors keep the context needed to report which path caused the problem: the offending input
+/// string when constructing a path, and the virtual path, base, and resolved path when accessing
+/// the filesystem.
+///
+/// ```
+/// use vpath::{Dirname, Error};
+///
+/// let err = "/etc".parse::<Dirname>().unwrap_err();
+/// assert_eq!(err.input(), Some("/etc"));
+/// assert_eq!(err.to_string(), "invalid dirname '/etc': dirname cannot be absolute");
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An [`AbsolutePath`](crate::AbsolutePath) could not be constructed
+    AbsolutePath {
+        /// The offending input, if known
+        input: Option<String>,
+        /// The underlying error
+        source: AbsolutePathError,
+    },
+    /// A [`Dirname`](crate::Dirname) could not be constructed
+    Dirname {
+        /// The offending input, if known
+        input: Option<String>,
+        /// The underlying error
+        source: DirnameError,
+    },
+    /// A [`Filename`](crate::Filename) could not be constructed
+    Filename {
+        /// The offending input, if known
+        input: Option<String>,
+        /// The underlying error
+        source: FilenameError,
+    },
+    /// A [`Glob`](crate::Glob) could not be constructed
+    Glob {
+        /// The offending input, if known
+        input: Option<String>,
+        /// The underlying error
+        source: GlobError,
+    },
+    /// A prefix could not be removed from a path
+    StripPrefix {
+        /// The path that the prefix was removed from
+        path: PathBuf,
+        /// The prefix
+        prefix: PathBuf,
+        /// The underlying error
+        source: StripPrefixError,
+    },
+    /// A relative path was absolute or contained a `..` component where only plain names are
+    /// accepted
+    NotRelative {
+        /// The offending path
+        path: PathBuf,
+    },
+    /// A path could not be expanded
+    Expand(ExpandError),
+    /// An XDG base directory could not be located
+    Xdg(XdgError),
+    /// A path was not valid UTF-8
+    Utf8(Utf8Error),
+    /// A filesystem operation failed
+    Io {
+        /// The operation that failed, such as "canonicalize"
+        op: &'static str,
+        /// The relative part of the virtual path, if the operation was on a virtual path
+        virtual_path: Option<PathBuf>,
+        /// The base of the virtual path, if it had one
+        base: Option<PathBuf>,
+        /// The path on the filesystem, if there was one
+        resolved: Option<PathBuf>,
+        /// The underlying error
+        source: std::io::Error,
+    },
+    /// A path exists on the filesystem but is not the kind of entity its marker requires
+    KindMismatch {
+        /// The relative part of the virtual path
+        virtual_path: PathBuf,
+        /// The base of the virtual path, if it had one
+        base: Option<PathBuf>,
+        /// The path on the filesystem
+        resolved: PathBuf,
+        /// The kind required by the marker
+        expected: MarkerKind,
+        /// The type of the entity found on the filesystem, after following symbolic links
+        found: std::fs::FileType,
+    },
+}
+
+impl Error {
+    /// Creates an I/O error for an operation on a virtual path.
+    pub(crate) fn io<M: PathKind>(
+        op: &'static str,
+        path: &VirtualPath<M>,
+        source: std::io::Error,
+    ) -> Self {
+        Self::Io {
+            op,
+            virtual_path: Some(path.path.clone()),
+            base: path.has_base().then(|| path.base.clone()),
+            resolved: Some(path.to_path_buf()),
+            source,
+        }
+    }
+
+    /// Creates an error for a virtual path that is not the kind of entity its marker requires.
+    pub(crate) fn kind_mismatch<M: PathKind>(
+        path: &VirtualPath<M>,
+        found: std::fs::FileType,
+    ) -> Self {
+        Self::KindMismatch {
+            virtual_path: path.path.clone(),
+            base: path.has_base().then(|| path.base.clone()),
+            resolved: path.to_path_buf(),
+            expected: M::KIND,
+            found,
+        }
+    }
+
+    /// Creates an I/O error for an operation on a path outside of a virtual path.
+    pub(crate) fn io_path(op: &'static str, path: &Path, source: std::io::Error) -> Self {
+        Self::Io {
+            op,
+            virtual_path: None,
+            base: None,
+            resolved: Some(path.to_path_buf()),
+            source,
+        }
+    }
+
+    /// Returns the input string that could not be converted into a path, if known.
+    pub fn input(&self) -> Option<&str> {
+        match self {
+            Self::AbsolutePath { input, .. }
+            | Self::Dirname { input, .. }
+            | Self::Filename { input, .. }
+            | Self::Glob { input, .. } => input.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the relative part of the virtual path involved in the error, if any.
+    ///
+    /// `None` is returned for [`Error::StripPrefix`], since the path that a prefix was removed
+    /// from may be absolute. Match on the variant to get it.
+    pub fn virtual_path(&self) -> Option<&Path> {
+        match self {
+            Self::Io { virtual_path, .. } => virtual_path.as_deref(),
+            Self::KindMismatch { virtual_path, .. } => Some(virtual_path),
+            Self::NotRelative { path } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns the base of the virtual path involved in the error, if any.
+    pub fn base(&self) -> Option<&Path> {
+        match self {
+            Self::Io { base, .. } | Self::KindMismatch { base, .. } => base.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the path on the filesystem involved in the error, if any.
+    pub fn resolved_path(&self) -> Option<&Path> {
+        match self {
+            Self::Io { resolved, .. } => resolved.as_deref(),
+            Self::KindMismatch { resolved, .. } => Some(resolved),
+            Self::Utf8(e) => Some(e.path()),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying I/O error, if this error was caused by one.
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn fmt_input(
+    f: &mut std::fmt::Formatter<'_>,
+    what: &str,
+    input: &Option<String>,
+) -> std::fmt::Result {
+    match input {
+        Some(input) => write!(f, "invalid {what} '{input}'"),
+        None => write!(f, "invalid {what}"),
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AbsolutePath { input, source } => {
+                fmt_input(f, "absolute path", input)?;
+                write!(f, ": {source}")
+            }
+            Self::Dirname { input, source } => {
+                fmt_input(f, "dirname", input)?;
+                write!(f, ": {source}")
+            }
+            Self::Filename { input, source } => {
+                fmt_input(f, "filename", input)?;
+                write!(f, ": {source}")
+            }
+            Self::Glob { input, source } => {
+                fmt_input(f, "glob", input)?;
+                write!(f, ": {source}")
+            }
+            Self::StripPrefix {
+                path,
+                prefix,
+                source,
+            } => write!(
+                f,
+                "failed to strip prefix '{}' from '{}': {source}",
+                prefix.display(),
+                path.display()
+            ),
+            Self::NotRelative { path } => write!(
+                f,
+                "expected a relative path without '..' components, found '{}'",
+                path.display()
+            ),
+            Self::Expand(e) => write!(f, "failed to expand path: {e}"),
+            Self::Xdg(e) => write!(f, "failed to locate XDG directory: {e}"),
+            Self::Utf8(e) => e.fmt(f),
+            Self::Io {
+                op,
+                virtual_path,
+                resolved,
+                source,
+                ..
+            } => {
+                write!(f, "failed to {op}")?;
+                match (virtual_path, resolved) {
+                    (Some(virtual_path), Some(resolved)) => {
+                        write!(f, " {} ({})", virtual_path.display(), resolved.display())?
+                    }
+                    (None, Some(resolved)) => write!(f, " {}", resolved.display())?,
+                    (Some(virtual_path), None) => write!(f, " {}", virtual_path.display())?,
+                    (None, None) => (),
+                }
+                write!(f, ": {source}")
+            }
+            Self::KindMismatch {
+                virtual_path,
+                resolved,
+                expected,
+                found,
+                ..
+            } => {
+                let expected = match expected {
+                    MarkerKind::Dir => "a directory",
+                    MarkerKind::File => "a file",
+                    MarkerKind::Any => "a file or directory",
+                };
+                let found = if found.is_dir() {
+                    "a directory"
+                } else if found.is_file() {
+                    "a file"
+                } else {
+                    "another kind of entry"
+                };
+                write!(
+                    f,
+                    "expected {} ({}) to be {expected}, found {found}",
+                    virtual_path.display(),
+                    resolved.display()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::AbsolutePath { source, .. } => Some(source),
+            Self::Dirname { source, .. } => Some(source),
+            Self::Filename { source, .. } => Some(source),
+            Self::Glob { source, .. } => Some(source),
+            Self::StripPrefix { source, .. } => Some(source),
+            Self::Expand(e) => Some(e),
+            Self::Xdg(e) => Some(e),
+            Self::Utf8(e) => Some(e),
+            Self::Io { source, .. } => Some(source),
+            Self::NotRelative { .. } | Self::KindMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<AbsolutePathError> for Error {
+    fn from(source: AbsolutePathError) -> Self {
+        Self::AbsolutePath {
+            input: None,
+            source,
+        }
+    }
+}
+
+impl From<DirnameError> for Error {
+    fn from(source: DirnameError) -> Self {
+        Self::Dirname {
+            input: None,
+            source,
+        }
+    }
+}
+
+impl From<FilenameError> for Error {
+    fn from(source: FilenameError) -> Self {
+        Self::Filename {
+            input: None,
+            source,
+        }
+    }
+}
+
+impl From<GlobError> for Error {
+    fn from(source: GlobError) -> Self {
+        Self::Glob {
+            input: None,
+            source,
+        }
+    }
+}
+
+impl From<ExpandError> for Error {
+    fn from(e: ExpandError) -> Self {
+        Self::Expand(e)
+    }
+}
+
+impl From<XdgError> for Error {
+    fn from(e: XdgError) -> Self {
+        Self::Xdg(e)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(e: Utf8Error) -> Self {
+        Self::Utf8(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+    use crate::absolute::AbsolutePath;
+
+    #[test]
+    fn io_error_includes_virtual_and_resolved_paths() {
+        let base = AbsolutePath::try_from("/srv/out").unwrap();
+        let path = VirtualPath::default()
+            .with_file_raw("posts/first.html")
+            .with_base(&base);
+        let source = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file");
+
+        let err = Error::io("canonicalize", &path, source);
+        assert_eq!(
+            err.to_string(),
+            "failed to canonicalize posts/first.html (/srv/out/posts/first.html): No such file"
+        );
+        assert_eq!(err.base(), Some(Path::new("/srv/out")));
+        assert_eq!(err.virtual_path(), Some(Path::new("posts/first.html")));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn io_error_omits_missing_base() {
+        let path = VirtualPath::default().with_file_raw("first.html");
+        let source = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file");
+
+        let err = Error::io("read", &path, source);
+        assert_eq!(err.base(), None);
+    }
+
+    #[test]
+    fn strip_prefix_error_has_no_virtual_path() {
+        let base = AbsolutePath::try_from("/srv/out").unwrap();
+        let other = AbsolutePath::try_from("/home").unwrap();
+
+        let err = base.strip_prefix(&other).unwrap_err();
+        assert_eq!(err.virtual_path(), None);
+        assert!(matches!(err, Error::StripPrefix { path, .. } if path == Path::new("/srv/out")));
+    }
+
+    #[test]
+    fn conversion_error_without_input() {
+        let err = Error::from(FilenameError::Empty);
+        assert_eq!(err.input(), None);
+        assert_eq!(
+            err.to_string(),
+            "invalid filename: filename cannot be empty"
+        );
+    }
+}
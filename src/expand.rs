@@ -0,0 +1,311 @@
+use std::{
+    ffi::OsString,
+    path::{is_separator, PathBuf},
+};
+
+use crate::absolute::AbsolutePath;
+use crate::env::{Environment, SystemEnv};
+
+/// An error that may occur while expanding a path with
+/// [`AbsolutePath::expand`](crate::AbsolutePath::expand).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpandError {
+    /// A variable was referenced but not set
+    UnsetVariable(String),
+    /// A variable reference did not contain a valid name
+    InvalidVariable(String),
+    /// A `${` was not closed with `}`
+    UnclosedBrace,
+    /// `~` was used but the home directory could not be determined
+    NoHomeDir,
+    /// `~user` was used but the home directory of the user could not be determined
+    UnknownUser(String),
+    /// The expanded path was not absolute
+    NotAbsolute(PathBuf),
+}
+
+impl std::fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsetVariable(name) => write!(f, "environment variable '{name}' is not set"),
+            Self::InvalidVariable(name) => write!(f, "invalid variable reference '{name}'"),
+            Self::UnclosedBrace => write!(f, "missing closing '}}' in variable reference"),
+            Self::NoHomeDir => write!(f, "home directory could not be determined"),
+            Self::UnknownUser(user) => write!(f, "home directory of user '{user}' not found"),
+            Self::NotAbsolute(path) => {
+                write!(f, "expanded path '{}' must be absolute", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+impl AbsolutePath {
+    /// Expands `~`, `~user`, `$VAR`, `${VAR}`, and `${VAR:-default}` using the environment of the
+    /// current process.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::expand_with`].
+    pub fn expand(path: &str) -> Result<Self, ExpandError> {
+        Self::expand_with(path, &SystemEnv)
+    }
+
+    /// Expands `~`, `~user`, `$VAR`, `${VAR}`, and `${VAR:-default}` using the given environment.
+    ///
+    /// `~` is only expanded at the start of the path. `${VAR:-default}` uses `default` when `VAR`
+    /// is unset or empty, and `default` may itself reference variables, as in
+    /// `${XDG_DATA_HOME:-${HOME}/.local/share}`. A `$` that is not followed by a variable name is
+    /// kept as-is.
+    ///
+    /// ```
+    /// use vpath::{AbsolutePath, EnvMap};
+    ///
+    /// let env = EnvMap::new()
+    ///     .with_var("HOME", "/home/blog")
+    ///     .with_var("XDG_DATA_HOME", "/data");
+    ///
+    /// let path = AbsolutePath::expand_with("~/sites/blog", &env).unwrap();
+    /// assert_eq!(path.as_path().to_str(), Some("/home/blog/sites/blog"));
+    ///
+    /// let path = AbsolutePath::expand_with("$XDG_DATA_HOME/blog", &env).unwrap();
+    /// assert_eq!(path.as_path().to_str(), Some("/data/blog"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if a referenced variable is not set, a home directory cannot be
+    /// found, the syntax is invalid, or the expanded path is not absolute.
+    pub fn expand_with<E>(path: &str, env: &E) -> Result<Self, ExpandError>
+    where
+        E: Environment + ?Sized,
+    {
+        let mut expanded = OsString::new();
+
+        let mut rest = path;
+        if let Some(after_tilde) = path.strip_prefix('~') {
+            let end = after_tilde.find(is_separator).unwrap_or(after_tilde.len());
+            let user = &after_tilde[..end];
+            let home = if user.is_empty() {
+                env.home_dir().ok_or(ExpandError::NoHomeDir)?
+            } else {
+                env.user_home_dir(user)
+                    .ok_or_else(|| ExpandError::UnknownUser(user.to_string()))?
+            };
+            expanded.push(home);
+            rest = &after_tilde[end..];
+        }
+
+        expand_vars(rest, env, &mut expanded)?;
+
+        let expanded = PathBuf::from(expanded);
+        Self::try_from(&expanded).map_err(|_| ExpandError::NotAbsolute(expanded))
+    }
+}
+
+/// Expands the variables in `rest`, appending the result to `expanded`.
+fn expand_vars<E>(mut rest: &str, env: &E, expanded: &mut OsString) -> Result<(), ExpandError>
+where
+    E: Environment + ?Sized,
+{
+    while let Some(dollar) = rest.find('$') {
+        expanded.push(&rest[..dollar]);
+        let after_dollar = &rest[dollar + 1..];
+
+        if let Some(braced) = after_dollar.strip_prefix('{') {
+            let close = closing_brace(braced).ok_or(ExpandError::UnclosedBrace)?;
+            let reference = &braced[..close];
+            let (name, default) = match reference.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (reference, None),
+            };
+            if !is_variable_name(name) {
+                return Err(ExpandError::InvalidVariable(reference.to_string()));
+            }
+
+            match (env.var(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => {
+                    expand_vars(default, env, expanded)?
+                }
+                (Some(value), _) => expanded.push(value),
+                (None, Some(default)) => expand_vars(default, env, expanded)?,
+                (None, None) => return Err(ExpandError::UnsetVariable(name.to_string())),
+            }
+            rest = &braced[close + 1..];
+        } else {
+            let len = after_dollar
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after_dollar.len());
+            let name = &after_dollar[..len];
+            if name.is_empty() {
+                expanded.push("$");
+            } else {
+                let value = env
+                    .var(name)
+                    .ok_or_else(|| ExpandError::UnsetVariable(name.to_string()))?;
+                expanded.push(value);
+            }
+            rest = &after_dollar[len..];
+        }
+    }
+    expanded.push(rest);
+    Ok(())
+}
+
+/// Returns the index of the `}` that closes a `${`, skipping over nested `${...}` references.
+fn closing_brace(braced: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut after_dollar = false;
+    for (i, c) in braced.char_indices() {
+        match c {
+            '{' if after_dollar => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        after_dollar = c == '$';
+    }
+    None
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::env::EnvMap;
+
+    fn env() -> EnvMap {
+        EnvMap::new()
+            .with_var("HOME", "/home/blog")
+            .with_var("SITE", "blog")
+            .with_var("EMPTY", "")
+            .with_user_home("admin", "/home/admin")
+    }
+
+    fn expand(path: &str) -> Result<AbsolutePath, ExpandError> {
+        AbsolutePath::expand_with(path, &env())
+    }
+
+    #[test]
+    fn expands_tilde() {
+        assert_eq!(expand("~").unwrap().as_path(), Path::new("/home/blog"));
+        assert_eq!(
+            expand("~/sites").unwrap().as_path(),
+            Path::new("/home/blog/sites")
+        );
+    }
+
+    #[test]
+    fn expands_tilde_user() {
+        assert_eq!(
+            expand("~admin/sites").unwrap().as_path(),
+            Path::new("/home/admin/sites")
+        );
+        assert_eq!(
+            expand("~nobody/sites").unwrap_err(),
+            ExpandError::UnknownUser("nobody".to_string())
+        );
+    }
+
+    #[test]
+    fn only_expands_tilde_at_start() {
+        assert_eq!(expand("/srv/~").unwrap().as_path(), Path::new("/srv/~"));
+    }
+
+    #[test]
+    fn expands_variables() {
+        assert_eq!(
+            expand("$HOME/sites/$SITE").unwrap().as_path(),
+            Path::new("/home/blog/sites/blog")
+        );
+        assert_eq!(
+            expand("${HOME}/${SITE}.d").unwrap().as_path(),
+            Path::new("/home/blog/blog.d")
+        );
+    }
+
+    #[test]
+    fn uses_default_when_variable_is_unset_or_empty() {
+        assert_eq!(
+            expand("${MISSING:-/srv}/www").unwrap().as_path(),
+            Path::new("/srv/www")
+        );
+        assert_eq!(
+            expand("${EMPTY:-/srv}/www").unwrap().as_path(),
+            Path::new("/srv/www")
+        );
+        assert_eq!(
+            expand("${HOME:-/srv}/www").unwrap().as_path(),
+            Path::new("/home/blog/www")
+        );
+    }
+
+    #[test]
+    fn expands_nested_defaults() {
+        assert_eq!(
+            expand("${MISSING:-${HOME}/www}/blog").unwrap().as_path(),
+            Path::new("/home/blog/www/blog")
+        );
+        assert_eq!(
+            expand("${MISSING:-${EMPTY:-/srv}}").unwrap().as_path(),
+            Path::new("/srv")
+        );
+        assert_eq!(
+            expand("${HOME:-${MISSING}}").unwrap().as_path(),
+            Path::new("/home/blog")
+        );
+        assert_eq!(
+            expand("${MISSING:-${HOME}").unwrap_err(),
+            ExpandError::UnclosedBrace
+        );
+    }
+
+    #[test]
+    fn keeps_lone_dollar() {
+        assert_eq!(expand("/srv/$/a").unwrap().as_path(), Path::new("/srv/$/a"));
+    }
+
+    #[test]
+    fn fails_when_variable_is_unset() {
+        assert_eq!(
+            expand("$MISSING/www").unwrap_err(),
+            ExpandError::UnsetVariable("MISSING".to_string())
+        );
+        assert_eq!(
+            expand("${MISSING}/www").unwrap_err(),
+            ExpandError::UnsetVariable("MISSING".to_string())
+        );
+    }
+
+    #[test]
+    fn fails_on_invalid_syntax() {
+        assert_eq!(expand("${HOME").unwrap_err(), ExpandError::UnclosedBrace);
+        assert_eq!(
+            expand("${}").unwrap_err(),
+            ExpandError::InvalidVariable(String::new())
+        );
+    }
+
+    #[test]
+    fn fails_when_result_is_not_absolute() {
+        assert_eq!(
+            expand("$SITE/www").unwrap_err(),
+            ExpandError::NotAbsolute(PathBuf::from("blog/www"))
+        );
+    }
+
+    #[test]
+    fn fails_when_home_is_unknown() {
+        let env = EnvMap::new();
+        assert_eq!(
+            AbsolutePath::expand_with("~/www", &env).unwrap_err(),
+            ExpandError::NoHomeDir
+        );
+    }
+}
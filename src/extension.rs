@@ -0,0 +1,68 @@
+use std::ffi::OsStr;
+
+/// Splits a file name into its "true" stem and its full extension.
+///
+/// The stem ends at the first `.` that is not part of a leading run of dots, so `archive.tar.gz`
+/// splits into `archive` and `tar.gz`, while `.bashrc` has no extension.
+pub(crate) fn split_extensions(name: &OsStr) -> (&OsStr, Option<&OsStr>) {
+    let bytes = name.as_encoded_bytes();
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != b'.')
+        .unwrap_or(bytes.len());
+
+    match bytes[start..].iter().position(|byte| *byte == b'.') {
+        Some(dot) => {
+            let dot = start + dot;
+            // SAFETY: the bytes are split immediately before and after an ASCII `.`.
+            unsafe {
+                (
+                    OsStr::from_encoded_bytes_unchecked(&bytes[..dot]),
+                    Some(OsStr::from_encoded_bytes_unchecked(&bytes[dot + 1..])),
+                )
+            }
+        }
+        None => (name, None),
+    }
+}
+
+/// Splits a full extension into its individual extensions.
+pub(crate) fn iter_extensions(extension: &OsStr) -> impl Iterator<Item = &OsStr> {
+    extension
+        .as_encoded_bytes()
+        .split(|byte| *byte == b'.')
+        // SAFETY: the bytes are split immediately before and after an ASCII `.`.
+        .map(|part| unsafe { OsStr::from_encoded_bytes_unchecked(part) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(name: &str) -> (&str, Option<&str>) {
+        let (stem, extension) = split_extensions(OsStr::new(name));
+        (
+            stem.to_str().unwrap(),
+            extension.map(|e| e.to_str().unwrap()),
+        )
+    }
+
+    #[test]
+    fn splits_at_first_dot() {
+        assert_eq!(split("archive.tar.gz"), ("archive", Some("tar.gz")));
+        assert_eq!(split("index.html"), ("index", Some("html")));
+        assert_eq!(split("README"), ("README", None));
+    }
+
+    #[test]
+    fn leading_dots_are_part_of_stem() {
+        assert_eq!(split(".bashrc"), (".bashrc", None));
+        assert_eq!(split(".config.toml"), (".config", Some("toml")));
+    }
+
+    #[test]
+    fn iterates_extensions() {
+        let extensions: Vec<_> = iter_extensions(OsStr::new("min.js.gz")).collect();
+        assert_eq!(extensions, ["min", "js", "gz"]);
+    }
+}
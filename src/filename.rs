@@ -1,13 +1,26 @@
-use std::path::{Path, PathBuf};
+use std::{
+    borrow::Borrow,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
 
 /// A filename component for a [`VirtualPath`](crate::VirtualPath).
 ///
 /// Filename components consist of a single filename and no parent directories.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Filename {
     pub(crate) name: PathBuf,
 }
 
+/// A borrowed [`Filename`].
+///
+/// This is to [`Filename`] what [`Path`] is to [`PathBuf`].
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct FilenameRef {
+    name: Path,
+}
+
 /// An error that may occur when constructing a [`Filename`].
 #[derive(Debug)]
 pub enum FilenameError {
@@ -15,6 +28,8 @@ pub enum FilenameError {
     Empty,
     /// The filename was absolute
     HasRoot,
+    /// The filename was required to be a single name, but contained directories
+    NotSingleName,
 }
 
 impl std::fmt::Display for FilenameError {
@@ -22,6 +37,7 @@ impl std::fmt::Display for FilenameError {
         match self {
             Self::Empty => write!(f, "filename cannot be empty"),
             Self::HasRoot => write!(f, "filename cannot be absolute"),
+            Self::NotSingleName => write!(f, "filename must be a single name"),
         }
     }
 }
@@ -48,6 +64,20 @@ impl TryFrom<&str> for Filename {
     }
 }
 
+impl std::str::FromStr for Filename {
+    type Err = crate::Error;
+
+    /// # Errors
+    ///
+    /// See [`Filename::try_from`]. The returned error includes the offending input.
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::try_from(path).map_err(|source| crate::Error::Filename {
+            input: Some(path.to_string()),
+            source,
+        })
+    }
+}
+
 impl TryFrom<PathBuf> for Filename {
     type Error = FilenameError;
 
@@ -77,6 +107,21 @@ impl TryFrom<&Path> for Filename {
     ///
     /// An `Err` will be returned if the filename is empty or has a root.
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        FilenameRef::new(path).map(ToOwned::to_owned)
+    }
+}
+
+impl FilenameRef {
+    /// Borrows `path` as a filename.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the filename is empty or has a root.
+    pub fn new<P>(path: &P) -> Result<&FilenameRef, FilenameError>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let path = path.as_ref();
         if path.components().next().is_none() {
             return Err(FilenameError::Empty);
         }
@@ -85,9 +130,71 @@ impl TryFrom<&Path> for Filename {
             return Err(FilenameError::HasRoot);
         }
 
-        Ok(Filename {
-            name: path.to_path_buf(),
-        })
+        Ok(Self::from_path_unchecked(path))
+    }
+
+    pub(crate) fn from_path_unchecked(path: &Path) -> &FilenameRef {
+        // SAFETY: `FilenameRef` is `repr(transparent)` over `Path`.
+        unsafe { &*(path as *const Path as *const FilenameRef) }
+    }
+
+    /// Returns this filename as a [`Path`].
+    pub fn as_path(&self) -> &Path {
+        &self.name
+    }
+}
+
+impl std::fmt::Display for Filename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.name.display(), f)
+    }
+}
+
+impl std::fmt::Display for FilenameRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.name.display(), f)
+    }
+}
+
+impl Deref for Filename {
+    type Target = FilenameRef;
+
+    fn deref(&self) -> &FilenameRef {
+        FilenameRef::from_path_unchecked(&self.name)
+    }
+}
+
+impl Borrow<FilenameRef> for Filename {
+    fn borrow(&self) -> &FilenameRef {
+        self
+    }
+}
+
+impl ToOwned for FilenameRef {
+    type Owned = Filename;
+
+    fn to_owned(&self) -> Filename {
+        Filename {
+            name: self.name.to_path_buf(),
+        }
+    }
+}
+
+impl AsRef<FilenameRef> for Filename {
+    fn as_ref(&self) -> &FilenameRef {
+        self
+    }
+}
+
+impl AsRef<FilenameRef> for FilenameRef {
+    fn as_ref(&self) -> &FilenameRef {
+        self
+    }
+}
+
+impl AsRef<Path> for FilenameRef {
+    fn as_ref(&self) -> &Path {
+        &self.name
     }
 }
 
@@ -112,4 +219,19 @@ mod tests {
         let file = Filename::try_from(PathBuf::from(""));
         assert!(file.is_err());
     }
+
+    #[test]
+    fn borrows_filename() {
+        let file = FilenameRef::new("index.md").unwrap();
+        assert_eq!(file.as_path(), Path::new("index.md"));
+
+        let owned: Filename = file.to_owned();
+        assert_eq!(owned.as_path(), Path::new("index.md"));
+    }
+
+    #[test]
+    fn fail_to_borrow_invalid_filename() {
+        assert!(FilenameRef::new("").is_err());
+        assert!(FilenameRef::new("/test").is_err());
+    }
 }
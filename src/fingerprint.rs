@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::marker::{File, FileMarker};
+use crate::relativekey::RelativeKey;
+use crate::sha256;
+use crate::virtualpath::VirtualPath;
+use crate::virtualpathref::VirtualPathRef;
+
+/// The number of hex digits of the digest used when none is specified.
+pub const DEFAULT_DIGEST_LEN: usize = 8;
+
+impl<K> VirtualPath<File<K>> {
+    /// Returns this path with a content digest inserted before the last extension.
+    ///
+    /// The digest is the SHA-256 hash of `contents` in lowercase hex, truncated to `len` digits
+    /// (at most 64). The path is returned unchanged if `len` is 0 or it has no file name.
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::VirtualPath;
+    ///
+    /// let app = VirtualPath::default().with_file_raw("js/app.js");
+    /// let app = app.fingerprinted(b"console.log('hi')", 8);
+    /// assert_eq!(app.to_path_buf(), PathBuf::from("js/app.d6885916.js"));
+    /// ```
+    pub fn fingerprinted(&self, contents: &[u8], len: usize) -> Self {
+        self.with_digest(&sha256::hex_digest(contents), len)
+    }
+
+    /// Reads this file and returns this path with a digest of the content inserted before the
+    /// last extension.
+    ///
+    /// The file is hashed as it is read rather than loaded into memory. See
+    /// [`fingerprinted`](Self::fingerprinted).
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the file cannot be read.
+    pub fn fingerprinted_file(&self, len: usize) -> Result<Self> {
+        let digest = std::fs::File::open(self.to_path_buf())
+            .and_then(sha256::digest_reader)
+            .map_err(|e| Error::io("read", self, e))?;
+        Ok(self.with_digest(&sha256::hex(&digest), len))
+    }
+
+    fn with_digest(&self, digest: &str, len: usize) -> Self {
+        let Some(stem) = self.path.file_stem().filter(|_| len > 0) else {
+            return self.clone();
+        };
+        let digest = &digest[..len.min(digest.len())];
+
+        let mut name = stem.to_os_string();
+        name.push(".");
+        name.push(digest);
+        if let Some(extension) = self.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+
+        let mut path = self.clone();
+        path.path.set_file_name(name);
+        path
+    }
+}
+
+/// Records the fingerprinted name of each file.
+///
+/// Entries are keyed by the relative part of the original path, so templates can look up the
+/// fingerprinted path using the path they already know.
+///
+/// ```
+/// use vpath::{FileMarker, FingerprintManifest, VirtualPath, VirtualPathRef};
+///
+/// let mut manifest = FingerprintManifest::new();
+/// let app = VirtualPath::default().with_file_raw("js/app.js");
+/// manifest.fingerprint(&app, b"console.log('hi')");
+///
+/// let fingerprinted = manifest.get(VirtualPathRef::new("js/app.js")).unwrap();
+/// assert_eq!(fingerprinted.to_string(), "js/app.d6885916.js");
+/// ```
+#[derive(Clone, Debug)]
+pub struct FingerprintManifest {
+    digest_len: usize,
+    entries: HashMap<RelativeKey<FileMarker>, VirtualPath<FileMarker>>,
+}
+
+impl FingerprintManifest {
+    /// Creates an empty manifest using digests of [`DEFAULT_DIGEST_LEN`] digits.
+    pub fn new() -> Self {
+        Self::with_digest_len(DEFAULT_DIGEST_LEN)
+    }
+
+    /// Creates an empty manifest using digests of `len` digits (at most 64).
+    ///
+    /// A `len` of 0 records every file under its original name.
+    pub fn with_digest_len(len: usize) -> Self {
+        Self {
+            digest_len: len,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of digits used for digests.
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+
+    /// Fingerprints `path` using `contents` and records the result.
+    ///
+    /// The returned path keeps the base of `path`.
+    pub fn fingerprint(
+        &mut self,
+        path: &VirtualPath<FileMarker>,
+        contents: &[u8],
+    ) -> VirtualPath<FileMarker> {
+        let fingerprinted = path.fingerprinted(contents, self.digest_len);
+        self.insert(path, &fingerprinted);
+        fingerprinted
+    }
+
+    /// Reads the file at `path`, fingerprints it, and records the result.
+    ///
+    /// The returned path keeps the base of `path`.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the file cannot be read.
+    pub fn fingerprint_file(
+        &mut self,
+        path: &VirtualPath<FileMarker>,
+    ) -> Result<VirtualPath<FileMarker>> {
+        let fingerprinted = path.fingerprinted_file(self.digest_len)?;
+        self.insert(path, &fingerprinted);
+        Ok(fingerprinted)
+    }
+
+    /// Records that `original` was fingerprinted as `fingerprinted`.
+    ///
+    /// The paths may be given with or without a base. Only their relative parts are stored.
+    pub fn insert<P, Q>(&mut self, original: P, fingerprinted: Q)
+    where
+        P: AsRef<VirtualPathRef<FileMarker>>,
+        Q: AsRef<VirtualPathRef<FileMarker>>,
+    {
+        self.entries.insert(
+            RelativeKey(original.as_ref().to_virtual_path()),
+            fingerprinted.as_ref().to_virtual_path(),
+        );
+    }
+
+    /// Returns the fingerprinted path of `original`, without a base.
+    ///
+    /// `original` may be given with or without a base. Only its relative part is looked up.
+    pub fn get<P>(&self, original: P) -> Option<&VirtualPath<FileMarker>>
+    where
+        P: AsRef<VirtualPathRef<FileMarker>>,
+    {
+        self.entries.get(original.as_ref())
+    }
+
+    /// Returns an iterator over the original and fingerprinted paths, in no particular order.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&VirtualPath<FileMarker>, &VirtualPath<FileMarker>)> {
+        self.entries
+            .iter()
+            .map(|(original, fingerprinted)| (&original.0, fingerprinted))
+    }
+
+    /// Returns the number of recorded files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no files have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Default for FingerprintManifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::absolute::AbsolutePath;
+    use crate::testutil::temp_tree;
+
+    #[test]
+    fn inserts_digest_before_last_extension() {
+        let path = VirtualPath::default().with_file_raw("app.min.js");
+        let fingerprinted = path.fingerprinted(b"abc", 8);
+
+        assert_eq!(
+            fingerprinted.to_path_buf(),
+            PathBuf::from("app.min.ba7816bf.js")
+        );
+    }
+
+    #[test]
+    fn appends_digest_without_extension() {
+        let path = VirtualPath::default().with_file_raw("LICENSE");
+        let fingerprinted = path.fingerprinted(b"abc", 4);
+
+        assert_eq!(fingerprinted.to_path_buf(), PathBuf::from("LICENSE.ba78"));
+    }
+
+    #[test]
+    fn zero_digest_len_leaves_path_unchanged() {
+        let path = VirtualPath::default().with_file_raw("app.js");
+
+        assert_eq!(path.fingerprinted(b"abc", 0), path);
+    }
+
+    #[test]
+    fn digest_len_is_capped() {
+        let path = VirtualPath::default().with_file_raw("a.js");
+        let fingerprinted = path.fingerprinted(b"abc", 100);
+
+        assert_eq!(fingerprinted.file_stem().len(), "a.".len() + 64,);
+    }
+
+    #[test]
+    fn manifest_is_queried_by_relative_path() {
+        let output = AbsolutePath::try_from("/srv/out").unwrap();
+        let app = VirtualPath::default()
+            .with_file_raw("js/app.js")
+            .with_base(&output);
+
+        let mut manifest = FingerprintManifest::with_digest_len(4);
+        let fingerprinted = manifest.fingerprint(&app, b"abc");
+        assert_eq!(
+            fingerprinted.to_path_buf(),
+            PathBuf::from("/srv/out/js/app.ba78.js")
+        );
+
+        let unbased = VirtualPath::default().with_file_raw("js/app.js");
+        assert_eq!(
+            manifest.get(&unbased).unwrap().to_path_buf(),
+            PathBuf::from("js/app.ba78.js")
+        );
+        assert_eq!(manifest.len(), 1);
+    }
+
+    #[test]
+    fn fingerprints_file_contents() {
+        let (_dir, base) = temp_tree(&[]);
+        let path = VirtualPath::default()
+            .with_file_raw("app.js")
+            .with_base(&base);
+        let contents = vec![b'x'; 20_000];
+        std::fs::write(path.to_path_buf(), &contents).unwrap();
+
+        assert_eq!(
+            path.fingerprinted_file(8).unwrap(),
+            path.fingerprinted(&contents, 8)
+        );
+    }
+
+    #[test]
+    fn fails_to_fingerprint_missing_file() {
+        let base = AbsolutePath::try_from("/nonexistent/vpath").unwrap();
+        let path = VirtualPath::default()
+            .with_file_raw("app.js")
+            .with_base(&base);
+
+        let err = FingerprintManifest::new()
+            .fingerprint_file(&path)
+            .unwrap_err();
+        assert!(err.io_error().is_some());
+    }
+}
@@ -0,0 +1,308 @@
+use std::path::Path;
+
+/// A pattern matching relative paths.
+///
+/// Patterns are matched one path component at a time:
+///
+/// - `*` matches any run of characters within a component
+/// - `?` matches a single character within a component
+/// - `[abc]`, `[a-z]`, and `[!a-z]` match a single character in, or not in, a set
+/// - `**` as a whole component matches zero or more components
+/// - `\` matches the next character literally
+///
+/// ```
+/// use vpath::Glob;
+///
+/// let glob = Glob::new(".git/**").unwrap();
+/// assert!(glob.is_match(".git/refs/heads/main"));
+/// assert!(!glob.is_match("src/.git"));
+///
+/// let glob = Glob::new("**/*.min.[cj]s*").unwrap();
+/// assert!(glob.is_match("app.min.js"));
+/// assert!(glob.is_match("static/css/site.min.css"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Glob {
+    pattern: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Segment {
+    AnyDirs,
+    Tokens(Vec<Token>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    Star,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// An error that may occur when constructing a [`Glob`].
+#[derive(Debug)]
+pub enum GlobError {
+    /// No pattern provided
+    Empty,
+    /// The pattern was absolute
+    Absolute,
+    /// A `[` was not closed by a `]`
+    UnclosedClass,
+}
+
+impl std::fmt::Display for GlobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "glob cannot be empty"),
+            Self::Absolute => write!(f, "glob cannot be absolute"),
+            Self::UnclosedClass => write!(f, "glob has an unclosed character class"),
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+impl Glob {
+    /// Parses a glob pattern.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the pattern is empty, absolute, or has an unclosed character
+    /// class.
+    pub fn new(pattern: &str) -> Result<Self, GlobError> {
+        if pattern.is_empty() {
+            return Err(GlobError::Empty);
+        }
+        if pattern.starts_with('/') {
+            return Err(GlobError::Absolute);
+        }
+
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment {
+                "**" => Ok(Segment::AnyDirs),
+                segment => parse_tokens(segment).map(Segment::Tokens),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            segments,
+        })
+    }
+
+    /// Returns the pattern this glob was parsed from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns `true` if the relative `path` matches this glob.
+    pub fn is_match<P: AsRef<Path>>(&self, path: P) -> bool {
+        let names: Vec<_> = path
+            .as_ref()
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        let names: Vec<Vec<char>> = names.iter().map(|name| name.chars().collect()).collect();
+        match_segments(&self.segments, &names)
+    }
+}
+
+fn parse_tokens(segment: &str) -> Result<Vec<Token>, GlobError> {
+    let mut tokens = Vec::new();
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' => {
+                if tokens.last() == Some(&Token::Star) {
+                    continue;
+                }
+                Token::Star
+            }
+            '?' => Token::AnyChar,
+            '\\' => Token::Literal(chars.next().unwrap_or('\\')),
+            '[' => {
+                let mut negated = false;
+                let mut ranges = Vec::new();
+                let mut first = true;
+                loop {
+                    let c = chars.next().ok_or(GlobError::UnclosedClass)?;
+                    match c {
+                        '!' if first && !negated => {
+                            negated = true;
+                            continue;
+                        }
+                        ']' if !first => break,
+                        c => {
+                            let mut lookahead = chars.clone();
+                            match (lookahead.next(), lookahead.next()) {
+                                (Some('-'), Some(end)) if end != ']' => {
+                                    chars = lookahead;
+                                    ranges.push((c, end));
+                                }
+                                _ => ranges.push((c, c)),
+                            }
+                        }
+                    }
+                    first = false;
+                }
+                Token::Class { negated, ranges }
+            }
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn match_segments(segments: &[Segment], names: &[Vec<char>]) -> bool {
+    match_wildcards(
+        segments,
+        names,
+        |segment| matches!(segment, Segment::AnyDirs),
+        |segment, name| match segment {
+            Segment::Tokens(tokens) => match_tokens(tokens, name),
+            Segment::AnyDirs => unreachable!("`**` is matched by match_wildcards"),
+        },
+    )
+}
+
+fn match_tokens(tokens: &[Token], name: &[char]) -> bool {
+    match_wildcards(
+        tokens,
+        name,
+        |token| *token == Token::Star,
+        |token, c| match_char(token, *c),
+    )
+}
+
+/// Matches `items` against `pattern`, where a star matches any number of items.
+///
+/// When a match fails after a star, only the most recent star takes one more item, since any
+/// earlier star could match no more than what the later one can. This keeps matching quadratic
+/// rather than exponential in the number of stars.
+fn match_wildcards<P, T>(
+    pattern: &[P],
+    items: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // The position after the last star seen, and the first item it has not taken yet.
+    let mut retry = None;
+    while i < items.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            p += 1;
+            retry = Some((p, i));
+        } else if p < pattern.len() && matches(&pattern[p], &items[i]) {
+            p += 1;
+            i += 1;
+        } else if let Some((after_star, taken)) = retry {
+            p = after_star;
+            i = taken + 1;
+            retry = Some((after_star, i));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(is_star)
+}
+
+fn match_char(token: &Token, c: char) -> bool {
+    match token {
+        Token::Literal(literal) => *literal == c,
+        Token::AnyChar => true,
+        Token::Star => unreachable!("stars are matched by match_wildcards"),
+        Token::Class { negated, ranges } => {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+                != *negated
+        }
+    }
+}
+
+impl std::str::FromStr for Glob {
+    type Err = crate::Error;
+
+    /// # Errors
+    ///
+    /// See [`Glob::new`]. The returned error includes the offending input.
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Self::new(pattern).map_err(|source| crate::Error::Glob {
+            input: Some(pattern.to_string()),
+            source,
+        })
+    }
+}
+
+impl std::fmt::Display for Glob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str) -> Glob {
+        Glob::new(pattern).unwrap()
+    }
+
+    #[test]
+    fn wildcards_stay_within_a_component() {
+        assert!(glob("*.html").is_match("index.html"));
+        assert!(!glob("*.html").is_match("blog/index.html"));
+        assert!(glob("blog/*/index.html").is_match("blog/first/index.html"));
+        assert!(glob("?.txt").is_match("a.txt"));
+        assert!(!glob("?.txt").is_match("ab.txt"));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_components() {
+        let glob = glob("a/**/z");
+        assert!(glob.is_match("a/z"));
+        assert!(glob.is_match("a/b/c/z"));
+        assert!(!glob.is_match("b/z"));
+
+        assert!(Glob::new(".git/**").unwrap().is_match(".git"));
+    }
+
+    #[test]
+    fn many_wildcards_match_quickly() {
+        let name = "a".repeat(40);
+        assert!(!glob("a*a*a*a*a*a*a*a*a*a*b").is_match(&name));
+        assert!(glob("a*a*a*a*a*a*a*a*a*a*").is_match(&name));
+
+        let deep = vec!["a"; 40].join("/");
+        assert!(!glob("**/a/**/a/**/a/**/a/**/a/**/b").is_match(&deep));
+        assert!(glob("**/a/**/a/**/a/**/a/**/a").is_match(&deep));
+        assert!(glob("a/**/*a*/**").is_match(&deep));
+    }
+
+    #[test]
+    fn classes_and_escapes() {
+        assert!(glob("[a-c]x").is_match("bx"));
+        assert!(!glob("[!a-c]x").is_match("bx"));
+        assert!(glob("[]]").is_match("]"));
+        assert!(glob(r"\*").is_match("*"));
+        assert!(!glob(r"\*").is_match("a"));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(matches!(Glob::new(""), Err(GlobError::Empty)));
+        assert!(matches!(Glob::new("/a"), Err(GlobError::Absolute)));
+        assert!(matches!(Glob::new("[ab"), Err(GlobError::UnclosedClass)));
+
+        let err = "[ab".parse::<Glob>().unwrap_err();
+        assert_eq!(err.input(), Some("[ab"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::glob::Glob;
+
+/// A single line of an ignore file.
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// The rules read from the ignore files of one directory.
+#[derive(Debug)]
+pub(crate) struct IgnoreRules {
+    /// The directory containing the ignore files, relative to the walk root
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            rules: Vec::new(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Adds the rules of an ignore file using `.gitignore` syntax.
+    ///
+    /// Lines that are not valid patterns are skipped, as git does.
+    pub(crate) fn add(&mut self, contents: &str) {
+        self.rules.extend(contents.lines().filter_map(parse_rule));
+    }
+
+    /// Returns `Some(true)` if the last rule matching `path` ignores it, `Some(false)` if it
+    /// re-includes it, and `None` if no rule matches.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let path = path.strip_prefix(&self.dir).ok()?;
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(path))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let mut line = line.trim_end_matches('\r');
+    while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+    }
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+
+    let glob = if line.contains('/') {
+        Glob::new(line.trim_start_matches('/'))
+    } else {
+        Glob::new(&format!("**/{line}"))
+    };
+    Some(Rule {
+        glob: glob.ok()?,
+        negated,
+        dir_only,
+    })
+}
+
+/// The ignore rules that apply within a directory, including those of its parents.
+#[derive(Debug)]
+pub(crate) struct IgnoreStack {
+    parent: Option<Arc<IgnoreStack>>,
+    rules: IgnoreRules,
+}
+
+impl IgnoreStack {
+    /// Adds `rules` on top of `parent`, returning `parent` unchanged if there are no rules.
+    pub(crate) fn push(
+        parent: Option<Arc<IgnoreStack>>,
+        rules: IgnoreRules,
+    ) -> Option<Arc<IgnoreStack>> {
+        if rules.is_empty() {
+            return parent;
+        }
+        Some(Arc::new(IgnoreStack { parent, rules }))
+    }
+
+    /// Returns `true` if `path`, relative to the walk root, is ignored.
+    ///
+    /// Rules in deeper directories take precedence over rules in their parents.
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut stack = Some(self);
+        while let Some(current) = stack {
+            if let Some(ignored) = current.rules.matched(path, is_dir) {
+                return ignored;
+            }
+            stack = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(dir: &str, contents: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::new(PathBuf::from(dir));
+        rules.add(contents);
+        rules
+    }
+
+    #[test]
+    fn follows_gitignore_syntax() {
+        let rules = rules(
+            "",
+            "# comment\n\n*.swp\n/build\ndocs/*.html\nnode_modules/\n!keep.swp\n",
+        );
+        let ignored = |path: &str, is_dir| rules.matched(Path::new(path), is_dir);
+
+        assert_eq!(ignored("a/b/file.swp", false), Some(true));
+        assert_eq!(ignored("a/keep.swp", false), Some(false));
+        assert_eq!(ignored("build", true), Some(true));
+        assert_eq!(ignored("src/build", true), None);
+        assert_eq!(ignored("docs/index.html", false), Some(true));
+        assert_eq!(ignored("src/docs/index.html", false), None);
+        assert_eq!(ignored("web/node_modules", true), Some(true));
+        assert_eq!(ignored("web/node_modules", false), None);
+    }
+
+    #[test]
+    fn nested_rules_take_precedence() {
+        let root = IgnoreStack::push(None, rules("", "*.log\n"));
+        let nested = IgnoreStack::push(root.clone(), rules("logs", "!keep.log\n")).unwrap();
+
+        assert!(nested.is_ignored(Path::new("logs/debug.log"), false));
+        assert!(!nested.is_ignored(Path::new("logs/keep.log"), false));
+        assert!(root.unwrap().is_ignored(Path::new("keep.log"), false));
+    }
+}
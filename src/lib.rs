@@ -77,13 +77,66 @@
 //! ```
 
 mod absolute;
+mod atomic;
+mod backend;
+mod cleanup;
+mod component;
 mod dirname;
+mod env;
+mod error;
+mod expand;
+mod extension;
 mod filename;
+mod fingerprint;
+mod glob;
+mod ignore;
+mod macros;
 mod marker;
+mod metadata;
+mod mime;
+mod natural;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pathmap;
+mod readdir;
+mod relativekey;
+mod sha256;
+mod sync;
+#[cfg(test)]
+mod testutil;
+mod tree;
+mod utf8;
 mod virtualpath;
+mod virtualpathref;
+mod walk;
+mod xdg;
 
-pub use absolute::AbsolutePath;
-pub use dirname::Dirname;
-pub use filename::Filename;
-pub use marker::{DirMarker, FileMarker};
+pub use absolute::{AbsolutePath, AbsolutePathError};
+pub use atomic::StagedOutput;
+pub use backend::{Backend, DryRun, FsBackend, Operation};
+pub use cleanup::{CleanupReport, OrphanCleanup};
+pub use component::Component;
+pub use dirname::{Dirname, DirnameError, DirnameRef};
+pub use env::{EnvMap, Environment, SystemEnv};
+pub use error::{Error, Result};
+pub use expand::ExpandError;
+pub use filename::{Filename, FilenameError, FilenameRef};
+pub use fingerprint::{FingerprintManifest, DEFAULT_DIGEST_LEN};
+pub use glob::{Glob, GlobError};
+#[doc(hidden)]
+pub use macros::__private;
+pub use marker::{
+    AnyFile, AnyMarker, Css, DirMarker, File, FileKind, FileMarker, Html, JavaScript, Json,
+    Markdown, MarkerKind, PathKind, Toml, Yaml,
+};
+pub use mime::{Category, MimeRegistry};
+pub use pathmap::{VirtualPathMap, VirtualPathSet};
+pub use readdir::{Entry, ReadDir};
+pub use relativekey::RelativeKey;
+pub use sync::{Compare, SyncAction, SyncReport, Transform, TreeSync};
+pub use tree::{TreeNode, VirtualTree};
+pub use utf8::{Utf8AbsolutePath, Utf8Dirname, Utf8Error, Utf8Filename, Utf8VirtualPath};
 pub use virtualpath::VirtualPath;
+pub use virtualpathref::VirtualPathRef;
+pub use walk::Walk;
+pub use xdg::{BaseDir, XdgError};
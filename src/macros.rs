@@ -0,0 +1,190 @@
+/// Creates a [`Dirname`](crate::Dirname) from a string literal that is validated at compile time.
+///
+/// ```
+/// use vpath::dirname;
+///
+/// let dir = dirname!("data/posts");
+/// assert_eq!(dir.to_string(), "data/posts");
+/// ```
+///
+/// Absolute or empty dirnames fail to compile:
+///
+/// ```compile_fail
+/// let dir = vpath::dirname!("/etc");
+/// ```
+///
+/// ```compile_fail
+/// let dir = vpath::dirname!("");
+/// ```
+#[macro_export]
+macro_rules! dirname {
+    ($name:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::__private::is_valid_relative($name),
+            ::core::concat!("invalid dirname: ", ::core::stringify!($name))
+        );
+        $crate::__private::dirname($name)
+    }};
+}
+
+/// Creates a [`Filename`](crate::Filename) from a string literal that is validated at compile
+/// time.
+///
+/// ```
+/// use vpath::filename;
+///
+/// let file = filename!("index.md");
+/// assert_eq!(file.to_string(), "index.md");
+/// ```
+///
+/// Absolute or empty filenames fail to compile:
+///
+/// ```compile_fail
+/// let file = vpath::filename!("/index.md");
+/// ```
+///
+/// ```compile_fail
+/// let file = vpath::filename!("");
+/// ```
+#[macro_export]
+macro_rules! filename {
+    ($name:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::__private::is_valid_relative($name),
+            ::core::concat!("invalid filename: ", ::core::stringify!($name))
+        );
+        $crate::__private::filename($name)
+    }};
+}
+
+/// Creates an [`AbsolutePath`](crate::AbsolutePath) from a string literal that is validated at
+/// compile time.
+///
+/// ```
+/// use vpath::abs;
+///
+/// let root = abs!("/srv/www");
+/// assert_eq!(root.to_string(), "/srv/www");
+/// ```
+///
+/// Relative paths fail to compile:
+///
+/// ```compile_fail
+/// let root = vpath::abs!("srv/www");
+/// ```
+#[macro_export]
+macro_rules! abs {
+    ($path:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::__private::is_absolute($path),
+            ::core::concat!("path must be absolute: ", ::core::stringify!($path))
+        );
+        $crate::__private::absolute_path($path)
+    }};
+}
+
+/// Creates a [`VirtualPath`](crate::VirtualPath) from string literals separated by `/` that are
+/// validated at compile time.
+///
+/// The final literal is a file and produces a `VirtualPath<FileMarker>`. End with a trailing `/`
+/// to produce a `VirtualPath<DirMarker>` instead.
+///
+/// ```
+/// # use std::path::PathBuf;
+/// use vpath::{vpath, DirMarker, FileMarker, VirtualPath};
+///
+/// let post: VirtualPath<FileMarker> = vpath!("data" / "posts" / "first.md");
+/// assert_eq!(post.to_path_buf(), PathBuf::from("data/posts/first.md"));
+///
+/// let posts: VirtualPath<DirMarker> = vpath!("data" / "posts" /);
+/// assert_eq!(posts.to_path_buf(), PathBuf::from("data/posts"));
+/// ```
+///
+/// ```compile_fail
+/// let post = vpath::vpath!("/data" / "first.md");
+/// ```
+#[macro_export]
+macro_rules! vpath {
+    (@file $path:expr; $file:literal) => {
+        $path.with_file($crate::filename!($file))
+    };
+    (@file $path:expr; $dir:literal / $($rest:tt)+) => {
+        $crate::vpath!(@file $path.with_dir($crate::dirname!($dir)); $($rest)+)
+    };
+    ($($dir:literal /)+) => {
+        $crate::VirtualPath::default()$(.with_dir($crate::dirname!($dir)))+
+    };
+    ($($segment:tt)+) => {
+        $crate::vpath!(@file $crate::VirtualPath::default(); $($segment)+)
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use std::path::PathBuf;
+
+    use crate::absolute::AbsolutePath;
+    use crate::dirname::Dirname;
+    use crate::filename::Filename;
+
+    /// Mirrors [`std::path::Path::is_absolute`] for string literals.
+    pub const fn is_absolute(path: &str) -> bool {
+        let bytes = path.as_bytes();
+        if cfg!(windows) {
+            (bytes.len() >= 2 && is_separator(bytes[0]) && is_separator(bytes[1]))
+                || (bytes.len() >= 3
+                    && bytes[0].is_ascii_alphabetic()
+                    && bytes[1] == b':'
+                    && is_separator(bytes[2]))
+        } else {
+            !bytes.is_empty() && bytes[0] == b'/'
+        }
+    }
+
+    const fn is_separator(byte: u8) -> bool {
+        byte == b'\\' || byte == b'/'
+    }
+
+    /// Returns `true` if `path` is a valid [`Dirname`] or [`Filename`].
+    pub const fn is_valid_relative(path: &str) -> bool {
+        !path.is_empty() && !is_absolute(path)
+    }
+
+    pub fn dirname(name: &str) -> Dirname {
+        Dirname {
+            name: PathBuf::from(name),
+        }
+    }
+
+    pub fn filename(name: &str) -> Filename {
+        Filename {
+            name: PathBuf::from(name),
+        }
+    }
+
+    pub fn absolute_path(path: &str) -> AbsolutePath {
+        AbsolutePath(PathBuf::from(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{Dirname, Filename};
+
+    #[test]
+    fn macros_match_runtime_validation() {
+        assert_eq!(dirname!("data"), Dirname::try_from("data").unwrap());
+        assert_eq!(
+            filename!("index.md"),
+            Filename::try_from("index.md").unwrap()
+        );
+    }
+
+    #[test]
+    fn builds_single_component_paths() {
+        assert_eq!(vpath!("index.md").to_path_buf(), PathBuf::from("index.md"));
+        assert_eq!(vpath!("data" /).to_path_buf(), PathBuf::from("data"));
+    }
+}
@@ -1,7 +1,223 @@
+use std::{cmp::Ordering, ffi::OsStr, marker::PhantomData};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The kind of entity a [`VirtualPath`](crate::VirtualPath) marker identifies, available at
+/// runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MarkerKind {
+    /// A directory
+    Dir,
+    /// A file
+    File,
+    /// Either a directory or a file
+    Any,
+}
+
+/// A marker for [`VirtualPath`](crate::VirtualPath).
+///
+/// This trait is sealed and implemented by [`DirMarker`], [`File`], and [`AnyMarker`].
+///
+/// ```
+/// use vpath::{MarkerKind, PathKind, VirtualPath};
+///
+/// fn describe<M: PathKind>(path: &VirtualPath<M>) -> String {
+///     let kind = match M::KIND {
+///         MarkerKind::Dir => "directory",
+///         MarkerKind::File => "file",
+///         MarkerKind::Any => "path",
+///     };
+///     format!("{kind} {path}")
+/// }
+///
+/// let path = VirtualPath::default().with_dir_raw("posts");
+/// assert_eq!(describe(&path), "directory posts");
+/// assert_eq!(describe(&path.into_any()), "path posts");
+/// ```
+pub trait PathKind: sealed::Sealed {
+    /// The kind of entity identified by this marker.
+    const KIND: MarkerKind;
+    /// `true` if this marker identifies a directory.
+    const IS_DIR: bool = matches!(Self::KIND, MarkerKind::Dir);
+    /// `true` if this marker identifies a file.
+    const IS_FILE: bool = matches!(Self::KIND, MarkerKind::File);
+}
+
 /// A [`VirtualPath`](crate::VirtualPath) marker used to identify the path as a directory.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct DirMarker;
 
+/// A [`VirtualPath`](crate::VirtualPath) marker used to identify the path as a file of kind `K`.
+///
+/// The kind restricts the extension of the file at the type level. Use
+/// [`try_into_kind`](crate::VirtualPath::try_into_kind) to check the extension of a file at
+/// runtime.
+///
+/// ```
+/// use vpath::{File, Html, Markdown, VirtualPath};
+///
+/// fn render(source: &VirtualPath<File<Markdown>>) -> VirtualPath<File<Html>> {
+///     source.clone().with_kind::<Html>()
+/// }
+///
+/// let post = VirtualPath::default()
+///     .with_file_raw("first.md")
+///     .try_into_kind::<Markdown>()
+///     .unwrap();
+/// assert_eq!(render(&post).to_string(), "first.html");
+/// ```
+pub struct File<K = AnyFile>(PhantomData<K>);
+
 /// A [`VirtualPath`](crate::VirtualPath) marker used to identify the path as a file.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct FileMarker;
+///
+/// This is a [`File`] of any kind. The constant of the same name is its value, so `FileMarker`
+/// can still be written where a marker value is expected. Unlike a unit struct, it cannot be
+/// used as a pattern; compare markers with `==` instead.
+///
+/// ```
+/// use vpath::{File, FileMarker};
+///
+/// let marker: File = FileMarker;
+/// assert_eq!(marker, FileMarker);
+/// ```
+pub type FileMarker = File<AnyFile>;
+
+/// The value of the [`FileMarker`](type@FileMarker) marker.
+#[allow(non_upper_case_globals)]
+pub const FileMarker: FileMarker = File(PhantomData);
+
+/// A [`VirtualPath`](crate::VirtualPath) marker used when the path may be either a directory or
+/// a file.
+///
+/// This allows directories and files to be stored in the same collection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnyMarker;
+
+impl sealed::Sealed for DirMarker {}
+impl<K> sealed::Sealed for File<K> {}
+impl sealed::Sealed for AnyMarker {}
+
+impl PathKind for DirMarker {
+    const KIND: MarkerKind = MarkerKind::Dir;
+}
+
+impl<K> PathKind for File<K> {
+    const KIND: MarkerKind = MarkerKind::File;
+}
+
+impl PathKind for AnyMarker {
+    const KIND: MarkerKind = MarkerKind::Any;
+}
+
+impl<K> Clone for File<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for File<K> {}
+
+impl<K> std::fmt::Debug for File<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "File<{}>", std::any::type_name::<K>())
+    }
+}
+
+impl<K> PartialEq for File<K> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<K> Eq for File<K> {}
+
+impl<K> std::hash::Hash for File<K> {
+    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+}
+
+impl<K> PartialOrd for File<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K> Ord for File<K> {
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// A kind of file, identified by its extension.
+///
+/// ```
+/// use vpath::{File, FileKind, VirtualPath};
+///
+/// struct Njk;
+///
+/// impl FileKind for Njk {
+///     const EXTENSIONS: &'static [&'static str] = &["njk"];
+/// }
+///
+/// let template = VirtualPath::default().with_file_raw("base.njk");
+/// let template: VirtualPath<File<Njk>> = template.try_into_kind().unwrap();
+/// ```
+pub trait FileKind {
+    /// The extensions of this kind. The first extension is used when converting to this kind.
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Returns `true` if `extension` belongs to this kind.
+    ///
+    /// Extensions are compared case-insensitively.
+    fn matches(extension: Option<&OsStr>) -> bool {
+        extension.and_then(OsStr::to_str).is_some_and(|extension| {
+            Self::EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+    }
+}
+
+/// A file of any kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnyFile;
+
+impl FileKind for AnyFile {
+    const EXTENSIONS: &'static [&'static str] = &[];
+
+    fn matches(_: Option<&OsStr>) -> bool {
+        true
+    }
+}
+
+macro_rules! file_kinds {
+    ($($(#[$doc:meta])* $kind:ident => [$($extension:literal),+];)+) => {
+        $(
+            $(#[$doc])*
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+            pub struct $kind;
+
+            impl FileKind for $kind {
+                const EXTENSIONS: &'static [&'static str] = &[$($extension),+];
+            }
+        )+
+    };
+}
+
+file_kinds! {
+    /// A Markdown file.
+    Markdown => ["md", "markdown"];
+    /// An HTML file.
+    Html => ["html", "htm"];
+    /// A CSS file.
+    Css => ["css"];
+    /// A JavaScript file.
+    JavaScript => ["js", "mjs", "cjs"];
+    /// A JSON file.
+    Json => ["json"];
+    /// A TOML file.
+    Toml => ["toml"];
+    /// A YAML file.
+    Yaml => ["yaml", "yml"];
+}
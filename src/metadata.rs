@@ -0,0 +1,184 @@
+use std::fs;
+use std::time::SystemTime;
+
+use crate::error::{Error, Result};
+use crate::marker::{MarkerKind, PathKind};
+use crate::virtualpath::VirtualPath;
+
+/// Metadata queries.
+///
+/// These resolve the full path, so a path without a base is intentionally resolved relative to
+/// the current working directory, like every other filesystem operation on a virtual path. Give
+/// the path a base with [`with_base`](VirtualPath::with_base) to avoid depending on the working
+/// directory.
+impl<M: PathKind> VirtualPath<M> {
+    /// Queries the metadata of the entity at this path, following symbolic links.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path does not exist or cannot be inspected.
+    pub fn metadata(&self) -> Result<fs::Metadata> {
+        fs::metadata(self.to_path_buf()).map_err(|e| Error::io("read metadata of", self, e))
+    }
+
+    /// Queries the metadata of the entity at this path, without following symbolic links.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path does not exist or cannot be inspected.
+    pub fn symlink_metadata(&self) -> Result<fs::Metadata> {
+        fs::symlink_metadata(self.to_path_buf()).map_err(|e| Error::io("read metadata of", self, e))
+    }
+
+    /// Returns the last modification time of the entity at this path.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path cannot be inspected or the platform does not record
+    /// modification times.
+    pub fn modified(&self) -> Result<SystemTime> {
+        self.metadata()?
+            .modified()
+            .map_err(|e| Error::io("read modification time of", self, e))
+    }
+
+    /// Returns the size in bytes of the entity at this path.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path does not exist or cannot be inspected.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    /// Returns the permissions of the entity at this path.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path does not exist or cannot be inspected.
+    pub fn permissions(&self) -> Result<fs::Permissions> {
+        Ok(self.metadata()?.permissions())
+    }
+
+    /// Returns `true` if this path points at a regular file, following symbolic links.
+    ///
+    /// Returns `false` if the path does not exist or cannot be inspected.
+    pub fn is_file(&self) -> bool {
+        self.to_path_buf().is_file()
+    }
+
+    /// Returns `true` if this path points at a directory, following symbolic links.
+    ///
+    /// Returns `false` if the path does not exist or cannot be inspected.
+    pub fn is_dir(&self) -> bool {
+        self.to_path_buf().is_dir()
+    }
+
+    /// Checks that the entity at this path is the kind required by the marker, returning its
+    /// metadata.
+    ///
+    /// Symbolic links are followed. A path marked with [`AnyMarker`](crate::AnyMarker) may be
+    /// either a file or a directory.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::create_dir(dir.path().join("index.html")).unwrap();
+    /// use vpath::{AbsolutePath, Error, VirtualPath};
+    ///
+    /// let output = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let index = VirtualPath::default().with_file_raw("index.html").with_base(&output);
+    ///
+    /// let err = index.check_kind().unwrap_err();
+    /// assert!(matches!(err, Error::KindMismatch { .. }));
+    /// assert!(err.to_string().ends_with("to be a file, found a directory"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::KindMismatch`] will be returned if the entity is a different kind. Any other
+    /// `Err` will be returned if the path does not exist or cannot be inspected.
+    pub fn check_kind(&self) -> Result<fs::Metadata> {
+        let metadata = self.metadata()?;
+        let file_type = metadata.file_type();
+        let matches = match M::KIND {
+            MarkerKind::Dir => file_type.is_dir(),
+            MarkerKind::File => file_type.is_file(),
+            MarkerKind::Any => file_type.is_dir() || file_type.is_file(),
+        };
+        if matches {
+            Ok(metadata)
+        } else {
+            Err(Error::kind_mismatch(self, file_type))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marker::DirMarker;
+    use crate::testutil::temp_tree;
+
+    fn root() -> (tempfile::TempDir, VirtualPath<DirMarker>) {
+        let (dir, base) = temp_tree(&[("posts/first.md", "# First")]);
+        (dir, VirtualPath::default().with_base(&base))
+    }
+
+    #[test]
+    fn queries_metadata() {
+        let (_dir, root) = root();
+        let post = root.clone().with_file_raw("posts/first.md");
+
+        assert_eq!(post.len().unwrap(), 7);
+        assert!(post.is_file());
+        assert!(!post.is_dir());
+        assert!(post.modified().unwrap() <= SystemTime::now());
+        assert!(!post.permissions().unwrap().readonly());
+        assert!(post.symlink_metadata().unwrap().is_file());
+
+        let missing = root.with_file_raw("missing.md");
+        assert!(!missing.is_file());
+        assert_eq!(
+            missing.len().unwrap_err().virtual_path(),
+            Some(std::path::Path::new("missing.md"))
+        );
+    }
+
+    #[test]
+    fn check_kind_compares_with_marker() {
+        let (_dir, root) = root();
+
+        assert!(root.clone().with_dir_raw("posts").check_kind().is_ok());
+        assert!(root
+            .clone()
+            .with_file_raw("posts/first.md")
+            .check_kind()
+            .is_ok());
+        assert!(root
+            .clone()
+            .with_dir_raw("posts")
+            .into_any()
+            .check_kind()
+            .is_ok());
+
+        let err = root
+            .clone()
+            .with_dir_raw("posts/first.md")
+            .check_kind()
+            .unwrap_err();
+        match err {
+            Error::KindMismatch {
+                expected, found, ..
+            } => {
+                assert_eq!(expected, MarkerKind::Dir);
+                assert!(found.is_file());
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+
+        let err = root.with_file_raw("posts").check_kind().unwrap_err();
+        assert_eq!(err.virtual_path(), Some(std::path::Path::new("posts")));
+        assert!(err.io_error().is_none());
+    }
+}
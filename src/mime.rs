@@ -0,0 +1,229 @@
+use std::{collections::HashMap, ffi::OsStr, sync::OnceLock};
+
+use crate::marker::File;
+use crate::virtualpath::VirtualPath;
+
+/// A coarse category of file content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Category {
+    /// HTML, Markdown, XML, and other markup
+    Markup,
+    /// CSS and stylesheet sources
+    Stylesheet,
+    /// JavaScript, WebAssembly, and other scripts
+    Script,
+    /// Raster and vector images
+    Image,
+    /// Web fonts
+    Font,
+    /// Structured data such as JSON, TOML, and YAML
+    Data,
+    /// Audio and video
+    Media,
+    /// Compressed archives
+    Archive,
+    /// Documents such as PDFs and plain text
+    Document,
+}
+
+const BUILTIN: &[(&str, &str, Category)] = &[
+    ("html", "text/html", Category::Markup),
+    ("htm", "text/html", Category::Markup),
+    ("xhtml", "application/xhtml+xml", Category::Markup),
+    ("md", "text/markdown", Category::Markup),
+    ("markdown", "text/markdown", Category::Markup),
+    ("xml", "application/xml", Category::Markup),
+    ("rss", "application/rss+xml", Category::Markup),
+    ("atom", "application/atom+xml", Category::Markup),
+    ("css", "text/css", Category::Stylesheet),
+    ("scss", "text/x-scss", Category::Stylesheet),
+    ("sass", "text/x-sass", Category::Stylesheet),
+    ("js", "text/javascript", Category::Script),
+    ("mjs", "text/javascript", Category::Script),
+    ("cjs", "text/javascript", Category::Script),
+    ("ts", "text/typescript", Category::Script),
+    ("d.ts", "text/typescript", Category::Script),
+    ("wasm", "application/wasm", Category::Script),
+    ("png", "image/png", Category::Image),
+    ("jpg", "image/jpeg", Category::Image),
+    ("jpeg", "image/jpeg", Category::Image),
+    ("gif", "image/gif", Category::Image),
+    ("webp", "image/webp", Category::Image),
+    ("avif", "image/avif", Category::Image),
+    ("svg", "image/svg+xml", Category::Image),
+    ("ico", "image/vnd.microsoft.icon", Category::Image),
+    ("bmp", "image/bmp", Category::Image),
+    ("woff", "font/woff", Category::Font),
+    ("woff2", "font/woff2", Category::Font),
+    ("ttf", "font/ttf", Category::Font),
+    ("otf", "font/otf", Category::Font),
+    ("eot", "application/vnd.ms-fontobject", Category::Font),
+    ("json", "application/json", Category::Data),
+    ("jsonld", "application/ld+json", Category::Data),
+    ("webmanifest", "application/manifest+json", Category::Data),
+    ("map", "application/json", Category::Data),
+    ("toml", "application/toml", Category::Data),
+    ("yaml", "application/yaml", Category::Data),
+    ("yml", "application/yaml", Category::Data),
+    ("csv", "text/csv", Category::Data),
+    ("mp3", "audio/mpeg", Category::Media),
+    ("ogg", "audio/ogg", Category::Media),
+    ("wav", "audio/wav", Category::Media),
+    ("mp4", "video/mp4", Category::Media),
+    ("webm", "video/webm", Category::Media),
+    ("zip", "application/zip", Category::Archive),
+    ("tar", "application/x-tar", Category::Archive),
+    ("gz", "application/gzip", Category::Archive),
+    ("tar.gz", "application/gzip", Category::Archive),
+    ("tgz", "application/gzip", Category::Archive),
+    ("br", "application/x-brotli", Category::Archive),
+    ("zst", "application/zstd", Category::Archive),
+    ("pdf", "application/pdf", Category::Document),
+    ("txt", "text/plain", Category::Document),
+];
+
+/// A mapping from file extensions to MIME types and [`Category`]s.
+///
+/// Extensions are matched case-insensitively and may contain multiple parts, such as `tar.gz`.
+/// When looking up a path, the longest registered chain of extensions wins.
+///
+/// ```
+/// use vpath::{Category, MimeRegistry, VirtualPath};
+///
+/// let mut registry = MimeRegistry::builtin().clone();
+/// registry.insert("njk", "text/html", Category::Markup);
+///
+/// let template = VirtualPath::default().with_file_raw("layouts/base.NJK");
+/// assert_eq!(template.mime_type_in(&registry), Some("text/html"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MimeRegistry {
+    entries: HashMap<String, (String, Category)>,
+}
+
+impl MimeRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the built-in registry of common web formats.
+    pub fn builtin() -> &'static MimeRegistry {
+        static BUILTIN_REGISTRY: OnceLock<MimeRegistry> = OnceLock::new();
+        BUILTIN_REGISTRY.get_or_init(|| {
+            let mut registry = MimeRegistry::new();
+            for (extension, mime_type, category) in BUILTIN {
+                registry.insert(extension, *mime_type, *category);
+            }
+            registry
+        })
+    }
+
+    /// Registers an extension, replacing any existing entry.
+    ///
+    /// The extension should not include a leading `.`.
+    pub fn insert<S>(&mut self, extension: &str, mime_type: S, category: Category)
+    where
+        S: Into<String>,
+    {
+        self.entries
+            .insert(extension.to_ascii_lowercase(), (mime_type.into(), category));
+    }
+
+    /// Returns the MIME type and category registered for an exact extension.
+    pub fn get(&self, extension: &str) -> Option<(&str, Category)> {
+        self.entries
+            .get(&extension.to_ascii_lowercase())
+            .map(|(mime_type, category)| (mime_type.as_str(), *category))
+    }
+
+    /// Returns the MIME type and category for a full extension such as `min.js` or `tar.gz`.
+    ///
+    /// Each suffix of the extension chain is tried from longest to shortest.
+    pub fn lookup(&self, full_extension: &OsStr) -> Option<(&str, Category)> {
+        let full_extension = full_extension.to_str()?;
+        std::iter::once(full_extension)
+            .chain(
+                full_extension
+                    .match_indices('.')
+                    .map(|(i, _)| &full_extension[i + 1..]),
+            )
+            .find_map(|extension| self.get(extension))
+    }
+}
+
+impl<K> VirtualPath<File<K>> {
+    /// Returns the MIME type of this file from the [built-in](MimeRegistry::builtin) registry.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        self.mime_type_in(MimeRegistry::builtin())
+    }
+
+    /// Returns the category of this file from the [built-in](MimeRegistry::builtin) registry.
+    pub fn category(&self) -> Option<Category> {
+        self.category_in(MimeRegistry::builtin())
+    }
+
+    /// Returns the MIME type of this file from the given registry.
+    pub fn mime_type_in<'r>(&self, registry: &'r MimeRegistry) -> Option<&'r str> {
+        registry
+            .lookup(self.full_extension()?)
+            .map(|(mime_type, _)| mime_type)
+    }
+
+    /// Returns the category of this file from the given registry.
+    pub fn category_in(&self, registry: &MimeRegistry) -> Option<Category> {
+        registry
+            .lookup(self.full_extension()?)
+            .map(|(_, category)| category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marker::FileMarker;
+
+    fn file(name: &str) -> VirtualPath<FileMarker> {
+        VirtualPath::default().with_file_raw(name)
+    }
+
+    #[test]
+    fn looks_up_builtin_types() {
+        assert_eq!(file("index.html").mime_type(), Some("text/html"));
+        assert_eq!(file("index.html").category(), Some(Category::Markup));
+        assert_eq!(file("site.css").category(), Some(Category::Stylesheet));
+        assert_eq!(file("font.woff2").category(), Some(Category::Font));
+    }
+
+    #[test]
+    fn lookup_is_case_insensitive() {
+        assert_eq!(file("LOGO.PNG").mime_type(), Some("image/png"));
+    }
+
+    #[test]
+    fn prefers_longest_extension_chain() {
+        assert_eq!(file("app.min.js").mime_type(), Some("text/javascript"));
+        assert_eq!(file("types.d.ts").category(), Some(Category::Script));
+        assert_eq!(file("site.tar.gz").category(), Some(Category::Archive));
+        assert_eq!(file("site.css.gz").mime_type(), Some("application/gzip"));
+    }
+
+    #[test]
+    fn unknown_extensions_have_no_type() {
+        assert_eq!(file("Makefile").mime_type(), None);
+        assert_eq!(file("data.unknown").category(), None);
+    }
+
+    #[test]
+    fn registered_entries_override_builtin() {
+        let mut registry = MimeRegistry::builtin().clone();
+        registry.insert("MD", "text/x-markdown", Category::Markup);
+
+        assert_eq!(
+            file("post.md").mime_type_in(&registry),
+            Some("text/x-markdown")
+        );
+        assert_eq!(file("post.md").mime_type(), Some("text/markdown"));
+    }
+}
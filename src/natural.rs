@@ -0,0 +1,109 @@
+use std::{cmp::Ordering, path::Path};
+
+/// Compares two paths component by component, ordering runs of digits by their numeric value.
+///
+/// `file2` is ordered before `file10`. Only when two paths are otherwise equal is the first run
+/// of digits with fewer leading zeros ordered first, so `file02b` is still before `file2c`.
+pub(crate) fn natural_cmp(a: &Path, b: &Path) -> Ordering {
+    let mut a = a.components();
+    let mut b = b.components();
+    let mut leading_zeros = Ordering::Equal;
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) => {
+                let ordering = natural_cmp_bytes(
+                    a.as_os_str().as_encoded_bytes(),
+                    b.as_os_str().as_encoded_bytes(),
+                    &mut leading_zeros,
+                );
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (None, None) => return leading_zeros,
+        }
+    }
+}
+
+/// Compares two components, ignoring leading zeros.
+///
+/// The first difference in leading zeros is recorded in `leading_zeros` if none was recorded yet.
+fn natural_cmp_bytes(mut a: &[u8], mut b: &[u8], leading_zeros: &mut Ordering) -> Ordering {
+    while !a.is_empty() && !b.is_empty() {
+        let (a_chunk, a_rest) = split_chunk(a);
+        let (b_chunk, b_rest) = split_chunk(b);
+
+        let ordering = if a_chunk[0].is_ascii_digit() && b_chunk[0].is_ascii_digit() {
+            let (a_value, b_value) = (trim_zeros(a_chunk), trim_zeros(b_chunk));
+            if *leading_zeros == Ordering::Equal {
+                *leading_zeros = a_chunk.len().cmp(&b_chunk.len());
+            }
+            a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value))
+        } else {
+            a_chunk.cmp(b_chunk)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        a = a_rest;
+        b = b_rest;
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Splits off the leading run of either digits or non-digits.
+fn split_chunk(bytes: &[u8]) -> (&[u8], &[u8]) {
+    let is_digit = bytes[0].is_ascii_digit();
+    let len = bytes
+        .iter()
+        .position(|byte| byte.is_ascii_digit() != is_digit)
+        .unwrap_or(bytes.len());
+    bytes.split_at(len)
+}
+
+fn trim_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits
+        .iter()
+        .position(|digit| *digit != b'0')
+        .unwrap_or(digits.len());
+    &digits[zeros..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmp(a: &str, b: &str) -> Ordering {
+        natural_cmp(Path::new(a), Path::new(b))
+    }
+
+    #[test]
+    fn orders_numbers_by_value() {
+        assert_eq!(cmp("file2", "file10"), Ordering::Less);
+        assert_eq!(cmp("file10", "file2"), Ordering::Greater);
+        assert_eq!(cmp("file10.md", "file10.md"), Ordering::Equal);
+    }
+
+    #[test]
+    fn orders_leading_zeros_after_plain_numbers() {
+        assert_eq!(cmp("file2", "file02"), Ordering::Less);
+        assert_eq!(cmp("file002", "file3"), Ordering::Less);
+        assert_eq!(cmp("file02b", "file2c"), Ordering::Less);
+        assert_eq!(cmp("file2c", "file02b"), Ordering::Greater);
+        assert_eq!(cmp("a02/a", "a2/b"), Ordering::Less);
+        assert_eq!(cmp("a2/x01", "a02/x1"), Ordering::Less);
+    }
+
+    #[test]
+    fn compares_by_component() {
+        assert_eq!(cmp("a/file10", "a-b/file2"), Ordering::Less);
+        assert_eq!(cmp("post2/z", "post10/a"), Ordering::Less);
+        assert_eq!(cmp("a", "a/b"), Ordering::Less);
+    }
+}
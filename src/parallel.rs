@@ -0,0 +1,310 @@
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+
+use crate::error::{Error, Result};
+use crate::marker::FileMarker;
+use crate::virtualpath::VirtualPath;
+use crate::walk::{Pending, PendingDir, Walk};
+
+impl Walk {
+    /// Calls `f` on every file, walking directories and processing files across the rayon
+    /// thread pool.
+    ///
+    /// Files are visited in no particular order. Use [`par_collect`](Self::par_collect) or
+    /// [`par_map`](Self::par_map) when the order matters.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::create_dir_all(dir.path().join("posts")).unwrap();
+    /// # std::fs::write(dir.path().join("posts/a.md"), "").unwrap();
+    /// # std::fs::write(dir.path().join("posts/b.md"), "").unwrap();
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use vpath::{AbsolutePath, VirtualPath};
+    ///
+    /// let source = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let count = AtomicUsize::new(0);
+    /// VirtualPath::default()
+    ///     .with_base(&source)
+    ///     .walk()
+    ///     .par_for_each(|_| {
+    ///         count.fetch_add(1, Ordering::Relaxed);
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(count.into_inner(), 2);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if a directory cannot be read. No further directories are read
+    /// once that happens, although files that were already found are still visited. If several
+    /// directories fail, any one of their errors is returned.
+    ///
+    /// An entry that cannot be inspected, such as a broken link or a link that forms a loop, is
+    /// skipped like the sequential walk skips past it: the rest of the tree is still visited, and
+    /// the entry's error is returned once the walk has finished.
+    pub fn par_for_each<F>(self, f: F) -> Result<()>
+    where
+        F: Fn(VirtualPath<FileMarker>) + Sync + Send,
+    {
+        self.par_try_for_each(|file| {
+            f(file);
+            Ok(())
+        })
+    }
+
+    /// Calls the fallible `f` on every file across the rayon thread pool.
+    ///
+    /// See [`par_for_each`](Self::par_for_each).
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if a directory cannot be read or `f` returns an error. No
+    /// further directories are read once that happens, but `f` is still called on files that
+    /// were already found, so it may be called again after returning an error. Entries that
+    /// cannot be inspected are handled as in [`par_for_each`](Self::par_for_each).
+    pub fn par_try_for_each<F>(self, f: F) -> Result<()>
+    where
+        F: Fn(VirtualPath<FileMarker>) -> Result<()> + Sync + Send,
+    {
+        self.par_run(&f, &|_| ())
+    }
+
+    /// Collects every file across the rayon thread pool.
+    ///
+    /// The files are returned in the same order as the sequential walk, so the result is
+    /// reproducible.
+    ///
+    /// # Errors
+    ///
+    /// See [`par_for_each`](Self::par_for_each).
+    pub fn par_collect(self) -> Result<Vec<VirtualPath<FileMarker>>> {
+        Ok(self
+            .par_map(|_| ())?
+            .into_iter()
+            .map(|(file, ())| file)
+            .collect())
+    }
+
+    /// Calls `f` on every file across the rayon thread pool and collects the results.
+    ///
+    /// The results are returned in the same order as the sequential walk, paired with their
+    /// files.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::write(dir.path().join("a.md"), "# A").unwrap();
+    /// # std::fs::write(dir.path().join("b.md"), "# Bee").unwrap();
+    /// use vpath::{AbsolutePath, VirtualPath};
+    ///
+    /// let source = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let sizes: Vec<_> = VirtualPath::default()
+    ///     .with_base(&source)
+    ///     .walk()
+    ///     .par_map(|file| std::fs::metadata(file.to_path_buf()).unwrap().len())
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|(file, len)| (file.relative().as_path().display().to_string(), len))
+    ///     .collect();
+    /// assert_eq!(sizes, vec![("a.md".to_string(), 3), ("b.md".to_string(), 5)]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// See [`par_for_each`](Self::par_for_each).
+    pub fn par_map<T, F>(self, f: F) -> Result<Vec<(VirtualPath<FileMarker>, T)>>
+    where
+        T: Send,
+        F: Fn(&VirtualPath<FileMarker>) -> T + Sync + Send,
+    {
+        let batches = Mutex::new(Vec::new());
+        self.par_run(
+            &|file| {
+                let result = f(&file);
+                Ok((file, result))
+            },
+            &|batch| {
+                batches
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(batch)
+            },
+        )?;
+
+        let batches = batches.into_inner().unwrap_or_else(|e| e.into_inner());
+        let mut results: Vec<_> = batches.into_iter().flatten().collect();
+        results.par_sort_unstable_by(|(a, _), (b, _)| a.path.cmp(&b.path));
+        Ok(results)
+    }
+
+    /// Walks the tree, calling `f` on every file and passing the results of each directory to
+    /// `sink` in one batch.
+    fn par_run<T, F, S>(mut self, f: &F, sink: &S) -> Result<()>
+    where
+        T: Send,
+        F: Fn(VirtualPath<FileMarker>) -> Result<T> + Sync + Send,
+        S: Fn(Vec<T>) + Sync + Send,
+    {
+        let errors = Errors::default();
+        let roots = std::mem::take(&mut self.stack);
+        let walk = &self;
+        let errors_ref = &errors;
+        rayon::scope(|scope| {
+            let mut files = Vec::new();
+            for root in roots {
+                match root {
+                    Pending::Dir(dir) => {
+                        scope.spawn(move |scope| walk.visit(scope, dir, f, sink, errors_ref))
+                    }
+                    Pending::File(file) => files.push(file),
+                    Pending::Error(e) => record(&errors_ref.entry, e),
+                }
+            }
+            visit_files(files, f, sink, &errors_ref.fatal);
+        });
+
+        let into_inner =
+            |error: Mutex<Option<Error>>| error.into_inner().unwrap_or_else(|e| e.into_inner());
+        match into_inner(errors.fatal).or(into_inner(errors.entry)) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn visit<'s, T, F, S>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        dir: PendingDir,
+        f: &'s F,
+        sink: &'s S,
+        errors: &'s Errors,
+    ) where
+        T: Send,
+        F: Fn(VirtualPath<FileMarker>) -> Result<T> + Sync + Send,
+        S: Fn(Vec<T>) + Sync + Send,
+    {
+        if errors.fatal.lock().map_or(true, |error| error.is_some()) {
+            return;
+        }
+        let entries = match self.read_entries(dir) {
+            Ok(entries) => entries,
+            Err(e) => return record(&errors.fatal, e),
+        };
+
+        let mut files = Vec::new();
+        for entry in entries {
+            match entry {
+                Pending::Dir(dir) => {
+                    scope.spawn(move |scope| self.visit(scope, dir, f, sink, errors))
+                }
+                Pending::File(file) => files.push(file),
+                Pending::Error(e) => record(&errors.entry, e),
+            }
+        }
+        visit_files(files, f, sink, &errors.fatal);
+    }
+}
+
+/// Calls `f` on `files` in parallel and passes the successful results to `sink` in one batch.
+fn visit_files<T, F, S>(
+    files: Vec<VirtualPath<FileMarker>>,
+    f: &F,
+    sink: &S,
+    error: &Mutex<Option<Error>>,
+) where
+    T: Send,
+    F: Fn(VirtualPath<FileMarker>) -> Result<T> + Sync + Send,
+    S: Fn(Vec<T>) + Sync + Send,
+{
+    if files.is_empty() {
+        return;
+    }
+    let results: Vec<T> = files
+        .into_par_iter()
+        .filter_map(|file| f(file).map_err(|e| record(error, e)).ok())
+        .collect();
+    sink(results);
+}
+
+/// The errors found by a parallel walk.
+#[derive(Default)]
+struct Errors {
+    /// An unreadable directory or an error from `f`, which stops further directories being read
+    fatal: Mutex<Option<Error>>,
+    /// An entry that could not be inspected, which is returned once the walk has finished
+    entry: Mutex<Option<Error>>,
+}
+
+/// Keeps the first error.
+fn record(error: &Mutex<Option<Error>>, e: Error) {
+    let mut error = error.lock().unwrap_or_else(|e| e.into_inner());
+    error.get_or_insert(e);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::marker::DirMarker;
+    use crate::testutil::temp_tree;
+
+    fn tree() -> (tempfile::TempDir, VirtualPath<DirMarker>) {
+        let files: Vec<_> = (0..5)
+            .flat_map(|a| {
+                (0..5).flat_map(move |b| (0..4).map(move |file| format!("{a}/{b}/{file}.txt")))
+            })
+            .collect();
+        let files: Vec<_> = files.iter().map(|file| (file.as_str(), "")).collect();
+        let (dir, base) = temp_tree(&files);
+        (dir, VirtualPath::default().with_base(&base))
+    }
+
+    #[test]
+    fn par_collect_matches_sequential_walk() {
+        let (_dir, root) = tree();
+
+        let sequential: Vec<_> = root.walk().map(Result::unwrap).collect();
+        let parallel = root.walk().par_collect().unwrap();
+        assert_eq!(parallel.len(), 100);
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn par_try_for_each_returns_an_error() {
+        let (_dir, root) = tree();
+
+        let err = root
+            .walk()
+            .par_try_for_each(|file| {
+                fs::read_dir(file.to_path_buf())
+                    .map(|_| ())
+                    .map_err(|e| Error::io("read directory", &file, e))
+            })
+            .unwrap_err();
+        assert!(err.virtual_path().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_loops_are_reported_after_visiting_every_file() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let (dir, root) = tree();
+        std::os::unix::fs::symlink("..", dir.path().join("0/loop")).unwrap();
+
+        let (sequential, errors): (Vec<_>, Vec<_>) = root.walk().partition(Result::is_ok);
+        assert_eq!(sequential.len(), 100);
+        assert_eq!(errors.len(), 1);
+
+        let count = AtomicUsize::new(0);
+        let err = root
+            .walk()
+            .par_for_each(|_| {
+                count.fetch_add(1, Ordering::Relaxed);
+            })
+            .unwrap_err();
+        assert_eq!(count.into_inner(), 100);
+        assert_eq!(err.virtual_path(), Some(std::path::Path::new("0/loop")));
+    }
+}
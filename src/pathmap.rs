@@ -0,0 +1,559 @@
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::component::relative_names;
+use crate::error::Result;
+use crate::marker::PathKind;
+use crate::virtualpath::VirtualPath;
+use crate::virtualpathref::VirtualPathRef;
+
+/// A map keyed by the relative parts of virtual paths, stored as a trie of path components.
+///
+/// Inserting and looking up a path takes time proportional to its depth, and every key under a
+/// prefix can be visited without scanning the rest of the map. Keys are returned without a base,
+/// in order of their components.
+///
+/// Keys cannot have a `..` component, and looking one up finds nothing.
+///
+/// ```
+/// use vpath::{FileMarker, VirtualPath, VirtualPathMap};
+///
+/// let mut titles = VirtualPathMap::new();
+/// for (file, title) in [
+///     ("posts/2023/old.html", "Old"),
+///     ("posts/2024/new.html", "New"),
+///     ("posts/2024/newer.html", "Newer"),
+/// ] {
+///     titles.insert(&VirtualPath::default().with_file_raw(file), title)?;
+/// }
+///
+/// let recent: Vec<_> = titles.subtree("posts/2024").map(|(_, title)| *title).collect();
+/// assert_eq!(recent, vec!["New", "Newer"]);
+/// assert!(titles.contains_prefix("posts/2023"));
+/// assert!(!titles.contains_prefix("posts/2022"));
+/// # Ok::<(), vpath::Error>(())
+/// ```
+pub struct VirtualPathMap<M: PathKind, V> {
+    root: Node<V>,
+    len: usize,
+    _phantom: PhantomData<M>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<OsString, Node<V>>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.value.is_none() && self.children.is_empty()
+    }
+
+    /// Removes the value at `names`, pruning nodes left empty.
+    fn remove(&mut self, names: &[&OsStr]) -> Option<V> {
+        match names.split_first() {
+            None => self.value.take(),
+            Some((name, rest)) => {
+                let child = self.children.get_mut(*name)?;
+                let value = child.remove(rest);
+                if child.is_empty() {
+                    self.children.remove(*name);
+                }
+                value
+            }
+        }
+    }
+}
+
+impl<M: PathKind, V> VirtualPathMap<M, V> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `value` at the relative part of `path`, returning the previous value.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::NotRelative`](crate::Error::NotRelative) will be returned if the relative part
+    /// of `path` has a `..` component.
+    pub fn insert<P>(&mut self, path: &P, value: V) -> Result<Option<V>>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        let names = relative_names(path.as_ref().as_path())?;
+        Ok(self.insert_names(&names, value))
+    }
+
+    /// Returns the value at the relative part of `path`.
+    pub fn get<P>(&self, path: &P) -> Option<&V>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        self.node(path.as_ref().as_path())?.value.as_ref()
+    }
+
+    /// Returns the value at the relative part of `path` mutably.
+    pub fn get_mut<P>(&mut self, path: &P) -> Option<&mut V>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        relative_names(path.as_ref().as_path())
+            .ok()?
+            .into_iter()
+            .try_fold(&mut self.root, |node, name| node.children.get_mut(name))?
+            .value
+            .as_mut()
+    }
+
+    /// Returns `true` if the map has a value at the relative part of `path`.
+    pub fn contains_key<P>(&self, path: &P) -> bool
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        self.get(path).is_some()
+    }
+
+    /// Removes the value at the relative part of `path` and returns it.
+    pub fn remove<P>(&mut self, path: &P) -> Option<V>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        let names = relative_names(path.as_ref().as_path()).ok()?;
+        let value = self.root.remove(&names);
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Returns `true` if any key is `prefix` or lies under it.
+    ///
+    /// Prefixes are compared by whole components, so `posts/20` is not a prefix of
+    /// `posts/2024`.
+    pub fn contains_prefix<P: AsRef<Path>>(&self, prefix: P) -> bool {
+        self.node(prefix.as_ref()).is_some()
+    }
+
+    /// Returns the entries whose key is `prefix` or lies under it, in order of their components.
+    ///
+    /// Nothing is returned if `prefix` has a `..` component.
+    pub fn subtree<P: AsRef<Path>>(&self, prefix: P) -> impl Iterator<Item = (VirtualPath<M>, &V)> {
+        let start = relative_names(prefix.as_ref()).ok().and_then(|names| {
+            let node = names
+                .iter()
+                .try_fold(&self.root, |node, name| node.children.get(*name))?;
+            Some((names.into_iter().collect::<PathBuf>(), node))
+        });
+        let mut stack: Vec<(PathBuf, &Node<V>)> = start.into_iter().collect();
+        std::iter::from_fn(move || loop {
+            let (path, node) = stack.pop()?;
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|(name, child)| (path.join(name), child)),
+            );
+            if let Some(value) = &node.value {
+                return Some((unbased(path), value));
+            }
+        })
+    }
+
+    /// Returns every entry in order of their components.
+    pub fn iter(&self) -> impl Iterator<Item = (VirtualPath<M>, &V)> {
+        self.subtree("")
+    }
+
+    /// Returns every key in order of their components.
+    pub fn keys(&self) -> impl Iterator<Item = VirtualPath<M>> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns the entry with the longest key that is `path` or a parent of `path`.
+    ///
+    /// `None` is returned if `path` has a `..` component.
+    ///
+    /// ```
+    /// use vpath::{DirMarker, VirtualPath, VirtualPathMap};
+    ///
+    /// let mut layouts: VirtualPathMap<DirMarker, &str> = VirtualPathMap::new();
+    /// layouts.insert(&VirtualPath::default(), "default").unwrap();
+    /// layouts.insert(&VirtualPath::default().with_dir_raw("posts"), "post").unwrap();
+    ///
+    /// let (dir, layout) = layouts.longest_prefix("posts/2024/new.html").unwrap();
+    /// assert_eq!((dir.to_string().as_str(), *layout), ("posts", "post"));
+    /// assert_eq!(layouts.longest_prefix("about.html").unwrap().1, &"default");
+    /// ```
+    pub fn longest_prefix<P: AsRef<Path>>(&self, path: P) -> Option<(VirtualPath<M>, &V)> {
+        let mut node = &self.root;
+        let mut current = PathBuf::new();
+        let mut longest = node.value.as_ref().map(|value| (current.clone(), value));
+        for name in relative_names(path.as_ref()).ok()? {
+            let Some(child) = node.children.get(name) else {
+                break;
+            };
+            node = child;
+            current.push(name);
+            if let Some(value) = &node.value {
+                longest = Some((current.clone(), value));
+            }
+        }
+        longest.map(|(path, value)| (unbased(path), value))
+    }
+
+    fn insert_names(&mut self, names: &[&OsStr], value: V) -> Option<V> {
+        let node = names.iter().fold(&mut self.root, |node, name| {
+            node.children
+                .entry(name.to_os_string())
+                .or_insert_with(Node::new)
+        });
+        let previous = node.value.replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    fn node(&self, path: &Path) -> Option<&Node<V>> {
+        relative_names(path)
+            .ok()?
+            .into_iter()
+            .try_fold(&self.root, |node, name| node.children.get(name))
+    }
+}
+
+fn unbased<M: PathKind>(path: PathBuf) -> VirtualPath<M> {
+    VirtualPath {
+        base: PathBuf::new(),
+        path,
+        _phantom: PhantomData,
+    }
+}
+
+impl<M: PathKind, V: Clone> Clone for VirtualPathMap<M, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<M: PathKind, V: PartialEq> PartialEq for VirtualPathMap<M, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root
+    }
+}
+
+impl<M: PathKind, V: Eq> Eq for VirtualPathMap<M, V> {}
+
+impl<M: PathKind, V> Default for VirtualPathMap<M, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: PathKind, V: std::fmt::Debug> std::fmt::Debug for VirtualPathMap<M, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(key, value)| (key.path, value)))
+            .finish()
+    }
+}
+
+/// A set of the relative parts of virtual paths, stored as a trie of path components.
+///
+/// See [`VirtualPathMap`].
+///
+/// ```
+/// use vpath::{FileMarker, VirtualPath, VirtualPathSet};
+///
+/// let set = |files: &[&str]| -> vpath::Result<VirtualPathSet<FileMarker>> {
+///     let mut set = VirtualPathSet::new();
+///     for file in files {
+///         set.insert(&VirtualPath::default().with_file_raw(file))?;
+///     }
+///     Ok(set)
+/// };
+/// let expected = set(&["index.html", "posts/new.html"])?;
+/// let written = set(&["index.html", "posts/old.html"])?;
+///
+/// let stale: Vec<_> = written.difference(&expected).iter().map(|p| p.to_string()).collect();
+/// assert_eq!(stale, vec!["posts/old.html"]);
+/// assert_eq!(written.union(&expected).len(), 3);
+/// # Ok::<(), vpath::Error>(())
+/// ```
+pub struct VirtualPathSet<M: PathKind> {
+    map: VirtualPathMap<M, ()>,
+}
+
+impl<M: PathKind> VirtualPathSet<M> {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self {
+            map: VirtualPathMap::new(),
+        }
+    }
+
+    /// Returns the number of paths.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set has no paths.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Adds the relative part of `path`, returning `true` if it was not already present.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::NotRelative`](crate::Error::NotRelative) will be returned if the relative part
+    /// of `path` has a `..` component.
+    pub fn insert<P>(&mut self, path: &P) -> Result<bool>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        Ok(self.map.insert(path, ())?.is_none())
+    }
+
+    /// Returns `true` if the set contains the relative part of `path`.
+    pub fn contains<P>(&self, path: &P) -> bool
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        self.map.contains_key(path)
+    }
+
+    /// Removes the relative part of `path`, returning `true` if it was present.
+    pub fn remove<P>(&mut self, path: &P) -> bool
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+    {
+        self.map.remove(path).is_some()
+    }
+
+    /// Returns `true` if any path is `prefix` or lies under it.
+    ///
+    /// See [`VirtualPathMap::contains_prefix`].
+    pub fn contains_prefix<P: AsRef<Path>>(&self, prefix: P) -> bool {
+        self.map.contains_prefix(prefix)
+    }
+
+    /// Returns the paths that are `prefix` or lie under it, in order of their components.
+    pub fn subtree<'a, P>(&'a self, prefix: P) -> impl Iterator<Item = VirtualPath<M>> + 'a
+    where
+        P: AsRef<Path> + 'a,
+    {
+        self.map.subtree(prefix).map(|(path, _)| path)
+    }
+
+    /// Returns every path in order of their components.
+    pub fn iter(&self) -> impl Iterator<Item = VirtualPath<M>> + '_ {
+        self.map.keys()
+    }
+
+    /// Returns the longest path in the set that is `path` or a parent of `path`.
+    pub fn longest_prefix<P: AsRef<Path>>(&self, path: P) -> Option<VirtualPath<M>> {
+        self.map.longest_prefix(path).map(|(path, _)| path)
+    }
+
+    /// Returns the paths in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        union.insert_all(other.iter());
+        union
+    }
+
+    /// Returns the paths in this set that are not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = Self::new();
+        difference.insert_all(self.iter().filter(|path| !other.contains(path)));
+        difference
+    }
+
+    /// Returns the paths in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intersection = Self::new();
+        intersection.insert_all(self.iter().filter(|path| other.contains(path)));
+        intersection
+    }
+
+    /// Adds paths taken from another set, which only have plain names as components.
+    fn insert_all(&mut self, paths: impl Iterator<Item = VirtualPath<M>>) {
+        for path in paths {
+            let names: Vec<_> = path.path.iter().collect();
+            self.map.insert_names(&names, ());
+        }
+    }
+}
+
+impl<M: PathKind> Clone for VirtualPathSet<M> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<M: PathKind> PartialEq for VirtualPathSet<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<M: PathKind> Eq for VirtualPathSet<M> {}
+
+impl<M: PathKind> Default for VirtualPathSet<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: PathKind> std::fmt::Debug for VirtualPathSet<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set()
+            .entries(self.iter().map(|path| path.path))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::absolute::AbsolutePath;
+    use crate::marker::{DirMarker, FileMarker};
+
+    fn file(path: &str) -> VirtualPath<FileMarker> {
+        VirtualPath::default().with_file_raw(path)
+    }
+
+    fn set(files: &[&str]) -> VirtualPathSet<FileMarker> {
+        let mut set = VirtualPathSet::new();
+        for path in files {
+            set.insert(&file(path)).unwrap();
+        }
+        set
+    }
+
+    fn strings(paths: impl Iterator<Item = VirtualPath<FileMarker>>) -> Vec<String> {
+        paths.map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn map_inserts_and_removes_by_relative_part() {
+        let base = AbsolutePath::try_from("/srv/site").unwrap();
+        let mut map = VirtualPathMap::new();
+
+        assert_eq!(
+            map.insert(&file("a/b.html").with_base(&base), 1).unwrap(),
+            None
+        );
+        assert_eq!(map.insert(&file("./a/b.html"), 2).unwrap(), Some(1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(VirtualPathRef::new("a/b.html")), Some(&2));
+
+        *map.get_mut(&file("a/b.html")).unwrap() += 1;
+        assert_eq!(map.remove(&file("a/b.html")), Some(3));
+        assert_eq!(map.remove(&file("a/b.html")), None);
+        assert!(map.is_empty());
+        assert!(!map.contains_prefix("a"));
+    }
+
+    #[test]
+    fn set_queries_subtrees_by_component() {
+        let set = set(&[
+            "posts/2024/b.html",
+            "posts/2024/a.html",
+            "posts/20/c.html",
+            "index.html",
+        ]);
+
+        assert_eq!(
+            strings(set.subtree("posts/2024")),
+            vec!["posts/2024/a.html", "posts/2024/b.html"]
+        );
+        assert_eq!(
+            strings(set.iter()),
+            vec![
+                "index.html",
+                "posts/20/c.html",
+                "posts/2024/a.html",
+                "posts/2024/b.html"
+            ]
+        );
+        assert!(set.contains_prefix("posts"));
+        assert!(!set.contains_prefix("posts/202"));
+        assert_eq!(strings(set.subtree("index.html")), vec!["index.html"]);
+    }
+
+    #[test]
+    fn longest_prefix_stops_at_deepest_key() {
+        let mut dirs: VirtualPathSet<DirMarker> = VirtualPathSet::new();
+        for dir in ["docs", "docs/api"] {
+            dirs.insert(&VirtualPath::default().with_dir_raw(dir))
+                .unwrap();
+        }
+
+        let longest = |path| dirs.longest_prefix(path).map(|dir| dir.to_string());
+        assert_eq!(
+            longest("docs/api/v1/index.html").as_deref(),
+            Some("docs/api")
+        );
+        assert_eq!(longest("docs/guide.html").as_deref(), Some("docs"));
+        assert_eq!(longest("blog/index.html"), None);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&["1", "2", "x/3"]);
+        let b = set(&["2", "x/3", "4"]);
+
+        assert_eq!(strings(a.union(&b).iter()), vec!["1", "2", "4", "x/3"]);
+        assert_eq!(strings(a.difference(&b).iter()), vec!["1"]);
+        assert_eq!(strings(a.intersection(&b).iter()), vec!["2", "x/3"]);
+        assert_eq!(format!("{:?}", a.difference(&b)), r#"{"1"}"#);
+    }
+
+    #[test]
+    fn rejects_parent_components() {
+        let mut set = set(&["posts/a.html"]);
+
+        assert!(matches!(
+            set.insert(&file("posts/../b.html")),
+            Err(crate::Error::NotRelative { .. })
+        ));
+        assert!(!set.contains(&file("posts/../posts/a.html")));
+        assert!(!set.contains_prefix("../posts"));
+        assert_eq!(set.subtree("../posts").count(), 0);
+        assert_eq!(set.longest_prefix("posts/a.html/.."), None);
+        assert!(set.contains(&file("./posts/a.html")));
+    }
+}
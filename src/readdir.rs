@@ -0,0 +1,267 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::marker::{AnyMarker, DirMarker, FileMarker};
+use crate::virtualpath::VirtualPath;
+
+impl VirtualPath<DirMarker> {
+    /// Returns an iterator over the entries directly within this directory.
+    ///
+    /// Entries keep the base of this directory and extend its relative part. The order of the
+    /// entries depends on the platform; use [`ReadDir::sorted`] for a stable order.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::create_dir_all(dir.path().join("posts/first")).unwrap();
+    /// # std::fs::write(dir.path().join("posts/index.md"), "").unwrap();
+    /// use vpath::{AbsolutePath, Entry, VirtualPath};
+    ///
+    /// let source = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let posts = VirtualPath::default().with_dir_raw("posts").with_base(&source);
+    ///
+    /// for entry in posts.read_dir().unwrap().sorted().unwrap() {
+    ///     match entry {
+    ///         Entry::Dir(dir) => assert_eq!(dir.relative().to_string(), "posts/first"),
+    ///         Entry::File(file) => assert_eq!(file.relative().to_string(), "posts/index.md"),
+    ///         _ => unreachable!(),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the directory cannot be read.
+    pub fn read_dir(&self) -> Result<ReadDir> {
+        let inner =
+            fs::read_dir(self.to_path_buf()).map_err(|e| Error::io("read directory", self, e))?;
+        Ok(ReadDir {
+            dir: self.clone(),
+            inner,
+        })
+    }
+}
+
+/// An entry within a directory, returned by [`VirtualPath::read_dir`].
+///
+/// The kind of entry is determined without following symbolic links. Metadata is only fetched
+/// when requested.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Entry {
+    /// A regular file
+    File(VirtualPath<FileMarker>),
+    /// A directory
+    Dir(VirtualPath<DirMarker>),
+    /// A symbolic link, which may point at anything
+    Symlink(VirtualPath<AnyMarker>),
+    /// Any other kind of entry, such as a socket or device
+    Other(VirtualPath<AnyMarker>),
+}
+
+impl Entry {
+    /// Returns the relative part of the path of this entry.
+    pub fn as_path(&self) -> &Path {
+        match self {
+            Self::File(file) => &file.path,
+            Self::Dir(dir) => &dir.path,
+            Self::Symlink(path) | Self::Other(path) => &path.path,
+        }
+    }
+
+    /// Returns the full path of this entry.
+    pub fn to_path_buf(&self) -> PathBuf {
+        match self {
+            Self::File(file) => file.to_path_buf(),
+            Self::Dir(dir) => dir.to_path_buf(),
+            Self::Symlink(path) | Self::Other(path) => path.to_path_buf(),
+        }
+    }
+
+    /// Returns the name of this entry within its directory.
+    pub fn file_name(&self) -> &OsStr {
+        self.as_path().file_name().unwrap_or_default()
+    }
+
+    /// Returns `true` if this entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        matches!(self, Self::File(_))
+    }
+
+    /// Returns `true` if this entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::Dir(_))
+    }
+
+    /// Returns `true` if this entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, Self::Symlink(_))
+    }
+
+    /// Returns the path of this entry without its kind.
+    pub fn into_any(self) -> VirtualPath<AnyMarker> {
+        match self {
+            Self::File(file) => file.into_any(),
+            Self::Dir(dir) => dir.into_any(),
+            Self::Symlink(path) | Self::Other(path) => path,
+        }
+    }
+
+    /// Queries the metadata of this entry, without following symbolic links.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the metadata cannot be read.
+    pub fn metadata(&self) -> Result<fs::Metadata> {
+        let path = self.clone().into_any();
+        fs::symlink_metadata(path.to_path_buf())
+            .map_err(|e| Error::io("read metadata of", &path, e))
+    }
+
+    /// Follows a symbolic link, returning the kind of entry it points at.
+    ///
+    /// The path of the entry is unchanged. Entries that are not symbolic links are returned as is.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the link is broken or its target cannot be inspected.
+    pub fn follow(self) -> Result<Entry> {
+        match self {
+            Self::Symlink(path) => {
+                let file_type = fs::metadata(path.to_path_buf())
+                    .map_err(|e| Error::io("follow link", &path, e))?
+                    .file_type();
+                Ok(Self::new(path.base, path.path, file_type))
+            }
+            entry => Ok(entry),
+        }
+    }
+
+    fn new(base: PathBuf, path: PathBuf, file_type: fs::FileType) -> Self {
+        if file_type.is_file() {
+            Self::File(VirtualPath {
+                base,
+                path,
+                _phantom: PhantomData,
+            })
+        } else if file_type.is_dir() {
+            Self::Dir(VirtualPath {
+                base,
+                path,
+                _phantom: PhantomData,
+            })
+        } else {
+            let path = VirtualPath {
+                base,
+                path,
+                _phantom: PhantomData,
+            };
+            if file_type.is_symlink() {
+                Self::Symlink(path)
+            } else {
+                Self::Other(path)
+            }
+        }
+    }
+}
+
+/// An iterator over the entries of a directory.
+///
+/// This is created by [`VirtualPath::read_dir`].
+#[derive(Debug)]
+pub struct ReadDir {
+    dir: VirtualPath<DirMarker>,
+    inner: fs::ReadDir,
+}
+
+impl ReadDir {
+    /// Collects the remaining entries, sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if an entry cannot be read.
+    pub fn sorted(self) -> Result<Vec<Entry>> {
+        let mut entries = self.collect::<Result<Vec<_>>>()?;
+        entries.sort_by(|a, b| a.as_path().cmp(b.as_path()));
+        Ok(entries)
+    }
+}
+
+impl Iterator for ReadDir {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let read = |e| Error::io("read directory", &self.dir, e);
+        let entry = match self.inner.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(read(e))),
+        };
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => return Some(Err(read(e))),
+        };
+        Some(Ok(Entry::new(
+            self.dir.base.clone(),
+            self.dir.path.join(entry.file_name()),
+            file_type,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_tree;
+
+    #[test]
+    fn lists_entries_with_base_preserved() {
+        let (dir, base) = temp_tree(&[
+            ("site/blog/index.html", "hello"),
+            ("site/blog/post/deep.html", ""),
+        ]);
+        let blog = VirtualPath::default()
+            .with_dir_raw("site/blog")
+            .with_base(&base);
+        let entries = blog.read_dir().unwrap().sorted().unwrap();
+
+        assert_eq!(entries.len(), 2);
+        let Entry::File(index) = &entries[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(
+            index.relative().as_path(),
+            Path::new("site/blog/index.html")
+        );
+        assert_eq!(index.to_path_buf(), dir.path().join("site/blog/index.html"));
+        assert_eq!(entries[0].metadata().unwrap().len(), 5);
+        assert!(entries[1].is_dir());
+        assert_eq!(entries[1].file_name(), "post");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_reported_and_can_be_followed() {
+        let (dir, base) = temp_tree(&[("real/", "")]);
+        std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("broken")).unwrap();
+
+        let entries = VirtualPath::default()
+            .with_base(&base)
+            .read_dir()
+            .unwrap()
+            .sorted()
+            .unwrap();
+        let names: Vec<_> = entries.iter().map(Entry::file_name).collect();
+        assert_eq!(names, vec!["broken", "link", "real"]);
+
+        let mut entries = entries.into_iter();
+        let broken = entries.next().unwrap();
+        assert!(broken.is_symlink());
+        assert!(broken.follow().is_err());
+
+        let link = entries.next().unwrap().follow().unwrap();
+        assert!(link.is_dir());
+        assert_eq!(link.as_path(), Path::new("link"));
+    }
+}
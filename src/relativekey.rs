@@ -0,0 +1,142 @@
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use crate::marker::PathKind;
+use crate::virtualpath::VirtualPath;
+use crate::virtualpathref::VirtualPathRef;
+
+/// A [`VirtualPath`] that is compared, hashed, and ordered by its relative part only.
+///
+/// `VirtualPath` considers the base when comparing paths. Use `RelativeKey` to key a collection
+/// by the virtual part of paths that may have different bases.
+///
+/// ```
+/// use std::collections::HashSet;
+/// use vpath::{AbsolutePath, RelativeKey, VirtualPath};
+///
+/// let source = AbsolutePath::try_from("/home/blog/source").unwrap();
+/// let output = AbsolutePath::try_from("/home/blog/output").unwrap();
+/// let post = VirtualPath::default().with_file_raw("first.md");
+///
+/// let mut seen = HashSet::new();
+/// seen.insert(RelativeKey(post.with_base(&source)));
+///
+/// assert!(seen.contains(&RelativeKey(post.with_base(&output))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RelativeKey<M: PathKind>(pub VirtualPath<M>);
+
+impl<M: PathKind> RelativeKey<M> {
+    /// Returns the wrapped virtual path.
+    pub fn into_inner(self) -> VirtualPath<M> {
+        self.0
+    }
+}
+
+impl<M: PathKind> From<VirtualPath<M>> for RelativeKey<M> {
+    fn from(path: VirtualPath<M>) -> Self {
+        Self(path)
+    }
+}
+
+impl<M: PathKind> Deref for RelativeKey<M> {
+    type Target = VirtualPath<M>;
+
+    fn deref(&self) -> &VirtualPath<M> {
+        &self.0
+    }
+}
+
+impl<M: PathKind> Borrow<VirtualPathRef<M>> for RelativeKey<M> {
+    fn borrow(&self) -> &VirtualPathRef<M> {
+        self.0.relative()
+    }
+}
+
+impl<M: PathKind> PartialEq for RelativeKey<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.path == other.0.path
+    }
+}
+
+impl<M: PathKind> Eq for RelativeKey<M> {}
+
+impl<M: PathKind> Hash for RelativeKey<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.path.hash(state);
+    }
+}
+
+impl<M: PathKind> PartialOrd for RelativeKey<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M: PathKind> Ord for RelativeKey<M> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.path.cmp(&other.0.path)
+    }
+}
+
+impl<M: PathKind> std::fmt::Display for RelativeKey<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::absolute::AbsolutePath;
+    use crate::marker::FileMarker;
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn ignores_base_when_comparing_and_hashing() {
+        let source = AbsolutePath::try_from("/srv/source").unwrap();
+        let output = AbsolutePath::try_from("/srv/output").unwrap();
+        let post = VirtualPath::default().with_file_raw("posts/first.md");
+        let a = RelativeKey(post.with_base(&source));
+        let b = RelativeKey(post.with_base(&output));
+
+        assert_ne!(a.0, b.0);
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(hash(&a), hash(&b));
+        assert_ne!(
+            a,
+            RelativeKey(VirtualPath::default().with_file_raw("second.md"))
+        );
+    }
+
+    #[test]
+    fn borrowed_key_hashes_like_the_key() {
+        let base = AbsolutePath::try_from("/srv/source").unwrap();
+        let key: RelativeKey<FileMarker> = RelativeKey(
+            VirtualPath::default()
+                .with_file_raw("a.md")
+                .with_base(&base),
+        );
+        let borrowed: &VirtualPathRef<FileMarker> = key.borrow();
+
+        assert_eq!(hash(&key), hash(borrowed));
+
+        let mut map = HashMap::new();
+        map.insert(key, 1);
+        assert_eq!(map.get(VirtualPathRef::new("a.md")), Some(&1));
+        assert_eq!(map.get(VirtualPathRef::new("b.md")), None);
+    }
+}
@@ -0,0 +1,609 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{Backend, FsBackend};
+use crate::error::{Error, Result};
+use crate::marker::{DirMarker, FileMarker};
+use crate::relativekey::RelativeKey;
+use crate::sha256;
+use crate::virtualpath::VirtualPath;
+
+/// How [`TreeSync`] decides whether a target file is already up to date.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Compare {
+    /// Every file is written.
+    Always,
+    /// A copied file is unchanged if the target has the same size and is not older than the
+    /// source. A transformed file is unchanged if the target is not older than the source.
+    #[default]
+    SizeAndMtime,
+    /// A file is unchanged if the target has the same SHA-256 hash as the content that would be
+    /// written.
+    Hash,
+}
+
+/// Produces the target contents of a file from its source contents.
+pub type Transform = Box<dyn FnOnce(&[u8]) -> Vec<u8>>;
+
+type MapFn<'a> = Box<dyn FnMut(&VirtualPath<FileMarker>) -> SyncAction + 'a>;
+
+/// What [`TreeSync`] does with a source file.
+///
+/// Paths are relative to the target root. Only their relative part is used, so a path keeps
+/// working if it still carries the base of the source file.
+pub enum SyncAction {
+    /// The file is not synced.
+    Skip,
+    /// The file is copied to the same relative path in the target.
+    Copy,
+    /// The file is copied to another path in the target.
+    CopyTo(VirtualPath<FileMarker>),
+    /// The file contents are transformed and written to a path in the target.
+    Transform {
+        /// The path written to
+        to: VirtualPath<FileMarker>,
+        /// Produces the target contents from the source contents
+        transform: Transform,
+    },
+}
+
+impl SyncAction {
+    /// Creates a [`SyncAction::Transform`] that writes the output of `transform` to `to`.
+    pub fn transform<F>(to: VirtualPath<FileMarker>, transform: F) -> Self
+    where
+        F: FnOnce(&[u8]) -> Vec<u8> + 'static,
+    {
+        Self::Transform {
+            to,
+            transform: Box::new(transform),
+        }
+    }
+}
+
+impl std::fmt::Debug for SyncAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Skip => f.write_str("Skip"),
+            Self::Copy => f.write_str("Copy"),
+            Self::CopyTo(to) => f.debug_tuple("CopyTo").field(to).finish(),
+            Self::Transform { to, .. } => f
+                .debug_struct("Transform")
+                .field("to", to)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Mirrors the files under one directory into another.
+///
+/// Each source file is passed to the mapping callback, relative to the source root and without a
+/// base, to decide whether it is skipped, copied, or transformed. Files are copied unchanged by
+/// default.
+///
+/// ```
+/// # let dir = tempfile::tempdir().unwrap();
+/// # std::fs::create_dir_all(dir.path().join("source/static/drafts")).unwrap();
+/// # std::fs::write(dir.path().join("source/static/site.css"), "body {  }").unwrap();
+/// # std::fs::write(dir.path().join("source/static/drafts/wip.css"), "").unwrap();
+/// use vpath::{AbsolutePath, SyncAction, TreeSync, VirtualPath};
+///
+/// let root = AbsolutePath::try_from(dir.path()).unwrap();
+/// let source = VirtualPath::default().with_dir_raw("source/static").with_base(&root);
+/// let target = VirtualPath::default().with_dir_raw("output/static").with_base(&root);
+///
+/// let report = TreeSync::new(&source, &target)
+///     .map(|file| {
+///         if file.relative().starts_with("drafts") {
+///             SyncAction::Skip
+///         } else if file.extension().is_some_and(|ext| ext == "css") {
+///             SyncAction::transform(file.clone(), |css| {
+///                 String::from_utf8_lossy(css).replace(' ', "").into_bytes()
+///             })
+///         } else {
+///             SyncAction::Copy
+///         }
+///     })
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(report.to_string(), "1 written, 0 unchanged, 1 skipped, 0 removed");
+/// let css = std::fs::read_to_string(dir.path().join("output/static/site.css")).unwrap();
+/// assert_eq!(css, "body{}");
+/// ```
+pub struct TreeSync<'a> {
+    source: VirtualPath<DirMarker>,
+    target: VirtualPath<DirMarker>,
+    map: MapFn<'a>,
+    compare: Compare,
+    delete_extraneous: bool,
+}
+
+impl<'a> TreeSync<'a> {
+    /// Creates a sync from `source` into `target` that copies every file.
+    pub fn new(source: &VirtualPath<DirMarker>, target: &VirtualPath<DirMarker>) -> Self {
+        Self {
+            source: source.clone(),
+            target: target.clone(),
+            map: Box::new(|_| SyncAction::Copy),
+            compare: Compare::default(),
+            delete_extraneous: false,
+        }
+    }
+
+    /// Sets the callback deciding what happens to each source file.
+    #[must_use]
+    pub fn map<F>(mut self, map: F) -> Self
+    where
+        F: FnMut(&VirtualPath<FileMarker>) -> SyncAction + 'a,
+    {
+        self.map = Box::new(map);
+        self
+    }
+
+    /// Sets how target files are checked for changes. Defaults to [`Compare::SizeAndMtime`].
+    #[must_use]
+    pub fn compare(mut self, compare: Compare) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    /// Removes files in the target that were not produced from a source file when `delete` is
+    /// `true`, along with the directories this leaves empty. Defaults to `false`.
+    ///
+    /// Symbolic links in the target are not followed, and an extraneous link is removed as a
+    /// link.
+    #[must_use]
+    pub fn delete_extraneous(mut self, delete: bool) -> Self {
+        self.delete_extraneous = delete;
+        self
+    }
+
+    /// Syncs the target on the real filesystem.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if a directory cannot be walked or a file cannot be inspected,
+    /// read, written, or removed. Files synced before the error are left in place.
+    pub fn run(self) -> Result<SyncReport> {
+        self.run_with(&mut FsBackend)
+    }
+
+    /// Syncs the target, performing every change through `backend`.
+    ///
+    /// The source and target are always inspected on the real filesystem, so a
+    /// [`DryRun`](crate::DryRun) backend records the changes that [`run`](Self::run) would make.
+    ///
+    /// # Errors
+    ///
+    /// See [`run`](Self::run).
+    pub fn run_with<B: Backend + ?Sized>(mut self, backend: &mut B) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        let mut produced = HashSet::new();
+
+        for file in self.source.walk() {
+            let file = file?;
+            let relative = relative_to(&file, &self.source);
+            let (to, transform) = match (self.map)(&relative) {
+                SyncAction::Skip => {
+                    report.skipped.push(file);
+                    continue;
+                }
+                SyncAction::Copy => (relative, None),
+                SyncAction::CopyTo(to) => (to, None),
+                SyncAction::Transform { to, transform } => (to, Some(transform)),
+            };
+            let target = self.target_file(&to);
+            produced.insert(RelativeKey(to));
+
+            let changed = match transform {
+                None => {
+                    if self.is_unchanged(&file, &target, true)? {
+                        false
+                    } else {
+                        backend.copy(&file, &target)?;
+                        true
+                    }
+                }
+                Some(transform) => {
+                    if self.is_unchanged(&file, &target, false)? {
+                        false
+                    } else {
+                        let contents = read(&file)?;
+                        let contents = transform(&contents);
+                        if self.compare == Compare::Hash
+                            && hash(&target)? == Some(sha256::digest(&contents))
+                        {
+                            false
+                        } else {
+                            backend.write(&target, &contents)?;
+                            true
+                        }
+                    }
+                }
+            };
+            if changed {
+                report.written.push(target);
+            } else {
+                report.unchanged.push(target);
+            }
+        }
+
+        if self.delete_extraneous && self.target.try_exists()? {
+            self.remove_extraneous(&produced, backend, &mut report)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Removes target files that were not produced, then the directories that this emptied,
+    /// deepest first.
+    ///
+    /// Links are removed as links, so nothing outside the target is touched.
+    fn remove_extraneous<B: Backend + ?Sized>(
+        &self,
+        produced: &HashSet<RelativeKey<FileMarker>>,
+        backend: &mut B,
+        report: &mut SyncReport,
+    ) -> Result<()> {
+        for file in self.target.walk().follow_links(false) {
+            let file = file?;
+            if !produced.contains(&RelativeKey(relative_to(&file, &self.target))) {
+                backend.remove_file(&file)?;
+                report.removed.push(file);
+            }
+        }
+
+        let retained: HashSet<&Path> = produced
+            .iter()
+            .flat_map(|file| file.path.ancestors().skip(1))
+            .collect();
+        let mut dirs: Vec<PathBuf> = report
+            .removed
+            .iter()
+            .flat_map(|file| {
+                let relative = file
+                    .path
+                    .strip_prefix(&self.target.path)
+                    .unwrap_or(&file.path);
+                relative.ancestors().skip(1).map(Path::to_path_buf)
+            })
+            .filter(|dir| !dir.as_os_str().is_empty() && !retained.contains(dir.as_path()))
+            .collect();
+        dirs.sort_by(|a, b| {
+            let depth = |path: &Path| path.components().count();
+            depth(b).cmp(&depth(a)).then_with(|| a.cmp(b))
+        });
+        dirs.dedup();
+
+        for dir in dirs {
+            let dir: VirtualPath<DirMarker> = VirtualPath {
+                base: self.target.base.clone(),
+                path: self.target.path.join(dir),
+                _phantom: PhantomData,
+            };
+            match backend.remove_dir(&dir) {
+                Ok(()) => report.removed_dirs.push(dir),
+                Err(e)
+                    if e.io_error().map(io::Error::kind) == Some(ErrorKind::DirectoryNotEmpty) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn target_file(&self, relative: &VirtualPath<FileMarker>) -> VirtualPath<FileMarker> {
+        VirtualPath {
+            base: self.target.base.clone(),
+            path: self.target.path.join(&relative.path),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if `target` is up to date with `source`.
+    ///
+    /// Hashes are only compared for copies, since a transform must run before its output can be
+    /// hashed.
+    fn is_unchanged(
+        &self,
+        source: &VirtualPath<FileMarker>,
+        target: &VirtualPath<FileMarker>,
+        copy: bool,
+    ) -> Result<bool> {
+        match self.compare {
+            Compare::Always => Ok(false),
+            Compare::Hash if !copy => Ok(false),
+            Compare::Hash => {
+                let target = hash(target)?;
+                Ok(target.is_some() && target == hash(source)?)
+            }
+            Compare::SizeAndMtime => {
+                let Some(target_meta) = metadata(target)? else {
+                    return Ok(false);
+                };
+                let source_meta = source_metadata(source)?;
+                let modified = |meta: &fs::Metadata, path| {
+                    meta.modified()
+                        .map_err(|e| Error::io("read modification time of", path, e))
+                };
+                let newer = modified(&target_meta, target)? >= modified(&source_meta, source)?;
+                Ok(newer && (!copy || target_meta.len() == source_meta.len()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for TreeSync<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TreeSync")
+            .field("source", &self.source)
+            .field("target", &self.target)
+            .field("compare", &self.compare)
+            .field("delete_extraneous", &self.delete_extraneous)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns `file` relative to `root`, without a base.
+fn relative_to(
+    file: &VirtualPath<FileMarker>,
+    root: &VirtualPath<DirMarker>,
+) -> VirtualPath<FileMarker> {
+    VirtualPath {
+        base: PathBuf::new(),
+        path: file
+            .path
+            .strip_prefix(&root.path)
+            .unwrap_or(&file.path)
+            .to_path_buf(),
+        _phantom: PhantomData,
+    }
+}
+
+fn read(file: &VirtualPath<FileMarker>) -> Result<Vec<u8>> {
+    fs::read(file.to_path_buf()).map_err(|e| Error::io("read", file, e))
+}
+
+fn source_metadata(file: &VirtualPath<FileMarker>) -> Result<fs::Metadata> {
+    fs::metadata(file.to_path_buf()).map_err(|e| Error::io("read metadata of", file, e))
+}
+
+/// Returns the metadata of `file`, or `None` if it does not exist.
+fn metadata(file: &VirtualPath<FileMarker>) -> Result<Option<fs::Metadata>> {
+    match fs::metadata(file.to_path_buf()) {
+        Ok(meta) => Ok(Some(meta)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io("read metadata of", file, e)),
+    }
+}
+
+/// Returns the hash of `file`, or `None` if it does not exist.
+fn hash(file: &VirtualPath<FileMarker>) -> Result<Option<[u8; 32]>> {
+    match fs::File::open(file.to_path_buf()).and_then(sha256::digest_reader) {
+        Ok(digest) => Ok(Some(digest)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::io("read", file, e)),
+    }
+}
+
+/// The files affected by a [`TreeSync`].
+///
+/// Skipped files are source paths. All other files are target paths.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    written: Vec<VirtualPath<FileMarker>>,
+    unchanged: Vec<VirtualPath<FileMarker>>,
+    skipped: Vec<VirtualPath<FileMarker>>,
+    removed: Vec<VirtualPath<FileMarker>>,
+    removed_dirs: Vec<VirtualPath<DirMarker>>,
+}
+
+impl SyncReport {
+    /// Returns the target files that were copied or written.
+    pub fn written(&self) -> &[VirtualPath<FileMarker>] {
+        &self.written
+    }
+
+    /// Returns the target files that were already up to date.
+    pub fn unchanged(&self) -> &[VirtualPath<FileMarker>] {
+        &self.unchanged
+    }
+
+    /// Returns the source files that the mapping callback skipped.
+    pub fn skipped(&self) -> &[VirtualPath<FileMarker>] {
+        &self.skipped
+    }
+
+    /// Returns the extraneous target files that were removed.
+    pub fn removed(&self) -> &[VirtualPath<FileMarker>] {
+        &self.removed
+    }
+
+    /// Returns the target directories that were removed because the sync emptied them, deepest
+    /// first.
+    pub fn removed_dirs(&self) -> &[VirtualPath<DirMarker>] {
+        &self.removed_dirs
+    }
+
+    /// Returns `true` if the sync did not change the target.
+    pub fn is_unchanged(&self) -> bool {
+        self.written.is_empty() && self.removed.is_empty() && self.removed_dirs.is_empty()
+    }
+}
+
+impl std::fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} written, {} unchanged, {} skipped, {} removed",
+            self.written.len(),
+            self.unchanged.len(),
+            self.skipped.len(),
+            self.removed.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::absolute::AbsolutePath;
+    use crate::backend::DryRun;
+    use crate::testutil::{names, temp_tree};
+
+    struct Fixture {
+        dir: tempfile::TempDir,
+        source: VirtualPath<DirMarker>,
+        target: VirtualPath<DirMarker>,
+    }
+
+    fn fixture(files: &[(&str, &str)]) -> Fixture {
+        let (dir, base) = temp_tree(files);
+        let root = VirtualPath::default().with_base(&base);
+        Fixture {
+            source: root.clone().with_dir_raw("source"),
+            target: root.with_dir_raw("target"),
+            dir,
+        }
+    }
+
+    #[test]
+    fn second_sync_leaves_unchanged_files_alone() {
+        for compare in [Compare::SizeAndMtime, Compare::Hash] {
+            let fixture = fixture(&[("source/a.txt", "a"), ("source/b/c.txt", "c")]);
+
+            let report = TreeSync::new(&fixture.source, &fixture.target)
+                .compare(compare)
+                .run()
+                .unwrap();
+            assert_eq!(
+                names(report.written()),
+                vec!["target/a.txt", "target/b/c.txt"]
+            );
+
+            let report = TreeSync::new(&fixture.source, &fixture.target)
+                .compare(compare)
+                .run()
+                .unwrap();
+            assert!(report.is_unchanged());
+            assert_eq!(report.unchanged().len(), 2);
+        }
+    }
+
+    #[test]
+    fn maps_files_and_deletes_extraneous() {
+        let fixture = fixture(&[
+            ("source/index.md", "# Home"),
+            ("source/drafts/wip.md", "wip"),
+            ("target/stale.html", "stale"),
+        ]);
+
+        let report = TreeSync::new(&fixture.source, &fixture.target)
+            .map(|file| {
+                if file.relative().starts_with("drafts") {
+                    SyncAction::Skip
+                } else {
+                    SyncAction::CopyTo(file.clone().with_extension("html"))
+                }
+            })
+            .delete_extraneous(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(names(report.written()), vec!["target/index.html"]);
+        assert_eq!(names(report.skipped()), vec!["source/drafts/wip.md"]);
+        assert_eq!(names(report.removed()), vec!["target/stale.html"]);
+        assert!(!fixture.dir.path().join("target/stale.html").exists());
+        assert!(!fixture.dir.path().join("target/drafts").exists());
+    }
+
+    #[test]
+    fn delete_extraneous_removes_emptied_directories() {
+        let fixture = fixture(&[
+            ("source/posts/new.html", ""),
+            ("target/posts/new.html", ""),
+            ("target/posts/old/a.html", ""),
+            ("target/archive/2020/b.html", ""),
+        ]);
+
+        let report = TreeSync::new(&fixture.source, &fixture.target)
+            .delete_extraneous(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            names(report.removed_dirs()),
+            vec!["target/archive/2020", "target/posts/old", "target/archive"]
+        );
+        assert!(fixture.dir.path().join("target/posts/new.html").exists());
+        assert!(!fixture.dir.path().join("target/archive").exists());
+        assert!(!fixture.dir.path().join("target/posts/old").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn delete_extraneous_does_not_follow_links_out_of_the_target() {
+        let fixture = fixture(&[
+            ("source/index.html", ""),
+            ("outside/precious.txt", ""),
+            ("target/sub/index.html", ""),
+        ]);
+        let dir = fixture.dir.path();
+        std::os::unix::fs::symlink(dir.join("outside"), dir.join("target/link")).unwrap();
+        std::os::unix::fs::symlink(dir.join("outside"), dir.join("target/sub/link")).unwrap();
+
+        let report = TreeSync::new(&fixture.source, &fixture.target)
+            .delete_extraneous(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            names(report.removed()),
+            vec!["target/link", "target/sub/index.html", "target/sub/link"]
+        );
+        assert!(dir.join("outside/precious.txt").exists());
+        assert!(!dir.join("target/link").exists());
+        assert!(!dir.join("target/sub").exists());
+    }
+
+    #[test]
+    fn delete_extraneous_keeps_targets_given_with_a_base() {
+        let fixture = fixture(&[("source/index.md", "# Home"), ("target/index.html", "old")]);
+
+        let report = TreeSync::new(&fixture.source, &fixture.target)
+            .map(|file| {
+                let based = file.with_base(&AbsolutePath::try_from("/elsewhere").unwrap());
+                SyncAction::CopyTo(based.with_extension("html"))
+            })
+            .compare(Compare::Always)
+            .delete_extraneous(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(names(report.written()), vec!["target/index.html"]);
+        assert!(report.removed().is_empty());
+        let html = fs::read_to_string(fixture.dir.path().join("target/index.html")).unwrap();
+        assert_eq!(html, "# Home");
+    }
+
+    #[test]
+    fn dry_run_records_changes_without_applying_them() {
+        let fixture = fixture(&[("source/a.txt", "a"), ("target/old.txt", "old")]);
+
+        let mut dry_run = DryRun::new();
+        let report = TreeSync::new(&fixture.source, &fixture.target)
+            .delete_extraneous(true)
+            .run_with(&mut dry_run)
+            .unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "1 written, 0 unchanged, 0 skipped, 1 removed"
+        );
+        assert_eq!(dry_run.len(), 2);
+        assert!(!fixture.dir.path().join("target/a.txt").exists());
+        assert!(fixture.dir.path().join("target/old.txt").exists());
+    }
+}
@@ -0,0 +1,35 @@
+//! Fixtures shared by the unit tests.
+
+use std::fs;
+
+use crate::absolute::AbsolutePath;
+use crate::marker::PathKind;
+use crate::virtualpath::VirtualPath;
+
+/// Creates a temporary directory containing `files` with their contents, returning the directory
+/// and its path.
+///
+/// Parent directories are created as needed, and a path ending in `/` creates an empty
+/// directory. The directory is removed when the returned guard is dropped.
+pub(crate) fn temp_tree(files: &[(&str, &str)]) -> (tempfile::TempDir, AbsolutePath) {
+    let dir = tempfile::tempdir().unwrap();
+    for (file, contents) in files {
+        let path = dir.path().join(file);
+        if file.ends_with('/') {
+            fs::create_dir_all(path).unwrap();
+        } else {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+    let base = AbsolutePath::try_from(dir.path()).unwrap();
+    (dir, base)
+}
+
+/// Returns the relative parts of `paths` as strings.
+pub(crate) fn names<M: PathKind>(paths: &[VirtualPath<M>]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.relative().as_path().display().to_string())
+        .collect()
+}
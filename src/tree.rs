@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use crate::component::relative_names;
+use crate::error::Result;
+use crate::marker::{MarkerKind, PathKind};
+use crate::virtualpathref::VirtualPathRef;
+
+/// A tree built from the relative parts of virtual paths.
+///
+/// Every inserted path becomes a node holding a payload. Missing parent directories are created
+/// as nodes without a payload. Children are ordered by name.
+///
+/// Paths with a `..` component cannot be inserted, and looking one up finds nothing.
+///
+/// ```
+/// use vpath::{VirtualPath, VirtualTree};
+///
+/// let mut menu = VirtualTree::new();
+/// for (file, title) in [
+///     ("blog/first.md", "First"),
+///     ("blog/second.md", "Second"),
+///     ("index.md", "Home"),
+/// ] {
+///     menu.insert(&VirtualPath::default().with_file_raw(file), title).unwrap();
+/// }
+///
+/// assert_eq!(menu.to_string(), "blog/\n  first.md\n  second.md\nindex.md\n");
+///
+/// let titles: Vec<_> = menu
+///     .node("blog")
+///     .unwrap()
+///     .depth_first()
+///     .filter_map(|node| node.payload())
+///     .collect();
+/// assert_eq!(titles, vec![&"First", &"Second"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VirtualTree<T> {
+    root: TreeNode<T>,
+}
+
+/// A node in a [`VirtualTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeNode<T> {
+    path: PathBuf,
+    kind: MarkerKind,
+    payload: Option<T>,
+    children: BTreeMap<OsString, TreeNode<T>>,
+}
+
+impl<T> VirtualTree<T> {
+    /// Creates an empty tree.
+    pub fn new() -> Self {
+        Self {
+            root: TreeNode::new(PathBuf::new(), MarkerKind::Dir),
+        }
+    }
+
+    /// Inserts the relative part of `path` with `payload`, returning the previous payload of the
+    /// node.
+    ///
+    /// The node takes the kind of the marker of `path`, unless it has children. A node with
+    /// children is always a [`MarkerKind::Dir`], so inserting below a file node turns it into a
+    /// directory.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::NotRelative`](crate::Error::NotRelative) will be returned if the relative part
+    /// of `path` has a `..` component.
+    pub fn insert<P, M>(&mut self, path: &P, payload: T) -> Result<Option<T>>
+    where
+        P: AsRef<VirtualPathRef<M>> + ?Sized,
+        M: PathKind,
+    {
+        let mut node = &mut self.root;
+        for name in relative_names(path.as_ref().as_path())? {
+            node.kind = MarkerKind::Dir;
+            let child_path = node.path.join(name);
+            node = node
+                .children
+                .entry(name.to_os_string())
+                .or_insert_with(|| TreeNode::new(child_path, MarkerKind::Dir));
+        }
+        if node.children.is_empty() {
+            node.kind = M::KIND;
+        }
+        Ok(node.payload.replace(payload))
+    }
+
+    /// Returns the root node, which has an empty path.
+    pub fn root(&self) -> &TreeNode<T> {
+        &self.root
+    }
+
+    /// Returns the node at the relative `path`, including its subtree.
+    pub fn node<P: AsRef<Path>>(&self, path: P) -> Option<&TreeNode<T>> {
+        relative_names(path.as_ref())
+            .ok()?
+            .into_iter()
+            .try_fold(&self.root, |node, name| node.children.get(name))
+    }
+
+    /// Returns the node at the relative `path` mutably.
+    pub fn node_mut<P: AsRef<Path>>(&mut self, path: P) -> Option<&mut TreeNode<T>> {
+        relative_names(path.as_ref())
+            .ok()?
+            .into_iter()
+            .try_fold(&mut self.root, |node, name| node.children.get_mut(name))
+    }
+
+    /// Returns the payload of the node at the relative `path`.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<&T> {
+        self.node(path)?.payload()
+    }
+
+    /// Returns the payload of the node at the relative `path` mutably.
+    pub fn get_mut<P: AsRef<Path>>(&mut self, path: P) -> Option<&mut T> {
+        self.node_mut(path)?.payload_mut()
+    }
+
+    /// Removes the node at the relative `path` along with its subtree.
+    ///
+    /// The root cannot be removed.
+    pub fn remove<P: AsRef<Path>>(&mut self, path: P) -> Option<TreeNode<T>> {
+        let names = relative_names(path.as_ref()).ok()?;
+        let (name, parents) = names.split_last()?;
+        let parent = parents
+            .iter()
+            .try_fold(&mut self.root, |node, name| node.children.get_mut(*name))?;
+        parent.children.remove(*name)
+    }
+
+    /// Removes every node for which `keep` returns `false`, along with its subtree.
+    ///
+    /// Nodes are visited parent first, so `keep` is not called on nodes below a removed node.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&TreeNode<T>) -> bool,
+    {
+        self.root.retain(&mut keep);
+    }
+
+    /// Removes directory nodes that have no payload and no children after pruning.
+    pub fn prune_empty(&mut self) {
+        self.root.prune_empty();
+    }
+
+    /// Returns every node except the root in depth-first order, parents before children.
+    pub fn depth_first(&self) -> impl Iterator<Item = &TreeNode<T>> {
+        self.root.depth_first().skip(1)
+    }
+
+    /// Returns every node except the root in breadth-first order.
+    pub fn breadth_first(&self) -> impl Iterator<Item = &TreeNode<T>> {
+        self.root.breadth_first().skip(1)
+    }
+
+    /// Returns the number of nodes, not counting the root.
+    pub fn len(&self) -> usize {
+        self.depth_first().count()
+    }
+
+    /// Returns `true` if the tree has no nodes other than the root.
+    pub fn is_empty(&self) -> bool {
+        self.root.children.is_empty()
+    }
+}
+
+impl<T> TreeNode<T> {
+    fn new(path: PathBuf, kind: MarkerKind) -> Self {
+        Self {
+            path,
+            kind,
+            payload: None,
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the relative path of this node.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the last component of the path of this node. The root has an empty name.
+    pub fn name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or_default()
+    }
+
+    /// Returns the number of components in the path of this node.
+    pub fn depth(&self) -> usize {
+        self.path.components().count()
+    }
+
+    /// Returns the kind of this node.
+    ///
+    /// Nodes created for missing parent directories are [`MarkerKind::Dir`].
+    pub fn kind(&self) -> MarkerKind {
+        self.kind
+    }
+
+    /// Returns the payload of this node, if it was inserted directly.
+    pub fn payload(&self) -> Option<&T> {
+        self.payload.as_ref()
+    }
+
+    /// Returns the payload of this node mutably.
+    pub fn payload_mut(&mut self) -> Option<&mut T> {
+        self.payload.as_mut()
+    }
+
+    /// Sets the payload of this node, returning the previous payload.
+    pub fn set_payload(&mut self, payload: T) -> Option<T> {
+        self.payload.replace(payload)
+    }
+
+    /// Returns the children of this node, ordered by name.
+    pub fn children(&self) -> impl Iterator<Item = &TreeNode<T>> {
+        self.children.values()
+    }
+
+    /// Returns the child of this node with the given name.
+    pub fn child<S: AsRef<OsStr>>(&self, name: S) -> Option<&TreeNode<T>> {
+        self.children.get(name.as_ref())
+    }
+
+    /// Returns `true` if this node has no children.
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns this node and every node below it in depth-first order, parents before children.
+    pub fn depth_first(&self) -> impl Iterator<Item = &TreeNode<T>> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.values().rev());
+            Some(node)
+        })
+    }
+
+    /// Returns this node and every node below it in breadth-first order.
+    pub fn breadth_first(&self) -> impl Iterator<Item = &TreeNode<T>> {
+        let mut queue = VecDeque::from([self]);
+        std::iter::from_fn(move || {
+            let node = queue.pop_front()?;
+            queue.extend(node.children.values());
+            Some(node)
+        })
+    }
+
+    fn retain<F>(&mut self, keep: &mut F)
+    where
+        F: FnMut(&TreeNode<T>) -> bool,
+    {
+        self.children.retain(|_, child| keep(child));
+        for child in self.children.values_mut() {
+            child.retain(keep);
+        }
+    }
+
+    fn prune_empty(&mut self) {
+        for child in self.children.values_mut() {
+            child.prune_empty();
+        }
+        self.children.retain(|_, child| {
+            child.kind != MarkerKind::Dir || child.payload.is_some() || !child.children.is_empty()
+        });
+    }
+}
+
+impl<T> Default for VirtualTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Display for VirtualTree<T> {
+    /// Renders the tree with two spaces of indentation per level. Directories end with `/`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in self.depth_first() {
+            let indent = (node.depth() - 1) * 2;
+            write!(f, "{:indent$}{}", "", node.name().to_string_lossy())?;
+            if node.kind == MarkerKind::Dir {
+                f.write_str("/")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::virtualpath::VirtualPath;
+
+    fn tree(files: &[&str]) -> VirtualTree<usize> {
+        let mut tree = VirtualTree::new();
+        for (i, file) in files.iter().enumerate() {
+            tree.insert(&VirtualPath::default().with_file_raw(file), i)
+                .unwrap();
+        }
+        tree
+    }
+
+    fn paths<'a>(nodes: impl Iterator<Item = &'a TreeNode<usize>>) -> Vec<String> {
+        nodes
+            .map(|node| node.path().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn iterates_depth_and_breadth_first() {
+        let tree = tree(&["b/c/d.md", "a.md", "b/e.md"]);
+
+        assert_eq!(
+            paths(tree.depth_first()),
+            vec!["a.md", "b", "b/c", "b/c/d.md", "b/e.md"]
+        );
+        assert_eq!(
+            paths(tree.breadth_first()),
+            vec!["a.md", "b", "b/c", "b/e.md", "b/c/d.md"]
+        );
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree.node("b/c").unwrap().kind(), MarkerKind::Dir);
+        assert_eq!(tree.node("b/e.md").unwrap().kind(), MarkerKind::File);
+    }
+
+    #[test]
+    fn stores_payloads_on_nodes() {
+        let mut tree = tree(&["blog/first.md"]);
+        assert_eq!(tree.get("blog/first.md"), Some(&0));
+        assert_eq!(tree.get("blog"), None);
+
+        tree.insert(&VirtualPath::default().with_dir_raw("blog"), 7)
+            .unwrap();
+        *tree.get_mut("blog/first.md").unwrap() += 1;
+        assert_eq!(tree.get("blog"), Some(&7));
+        assert_eq!(tree.get("blog/first.md"), Some(&1));
+        assert_eq!(
+            tree.insert(&VirtualPath::default().with_file_raw("blog/first.md"), 2)
+                .unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn prunes_nodes() {
+        let mut tree = tree(&["blog/drafts/wip.md", "blog/first.md", "about.md"]);
+
+        let removed = tree.remove("about.md").unwrap();
+        assert_eq!(removed.payload(), Some(&2));
+
+        tree.retain(|node| node.name() != "wip.md");
+        assert!(tree.node("blog/drafts").is_some());
+        tree.prune_empty();
+        assert_eq!(paths(tree.depth_first()), vec!["blog", "blog/first.md"]);
+        assert_eq!(tree.to_string(), "blog/\n  first.md\n");
+    }
+
+    #[test]
+    fn rejects_parent_components_and_keeps_files_without_children() {
+        let mut tree = tree(&["a/b.md"]);
+
+        let escaping = VirtualPath::default().with_file_raw("a/../c.md");
+        assert!(matches!(
+            tree.insert(&escaping, 1),
+            Err(crate::Error::NotRelative { .. })
+        ));
+        assert!(tree.node("a/../a").is_none());
+        assert!(tree.node("../a").is_none());
+        assert!(tree.remove("a/..").is_none());
+        assert_eq!(tree.get("./a/b.md"), Some(&0));
+
+        tree.insert(&VirtualPath::default().with_file_raw("a/b.md/c.md"), 1)
+            .unwrap();
+        assert_eq!(tree.node("a/b.md").unwrap().kind(), MarkerKind::Dir);
+        tree.insert(&VirtualPath::default().with_file_raw("a/b.md"), 2)
+            .unwrap();
+        assert_eq!(tree.node("a/b.md").unwrap().kind(), MarkerKind::Dir);
+        assert_eq!(tree.to_string(), "a/\n  b.md/\n    c.md\n");
+    }
+}
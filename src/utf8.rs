@@ -0,0 +1,495 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use crate::absolute::{AbsolutePath, AbsolutePathError};
+use crate::dirname::{Dirname, DirnameError, DirnameRef};
+use crate::filename::{Filename, FilenameError, FilenameRef};
+use crate::marker::{DirMarker, FileMarker, PathKind};
+use crate::virtualpath::VirtualPath;
+
+/// An error that occurs when a path is not valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Utf8Error {
+    path: PathBuf,
+    offset: usize,
+}
+
+impl Utf8Error {
+    /// Returns the path that was not valid UTF-8.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the byte offset of the first invalid byte in the path.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl std::fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "path '{}' is not valid UTF-8 at byte {}",
+            self.path.display(),
+            self.offset
+        )
+    }
+}
+
+impl std::error::Error for Utf8Error {}
+
+/// Checks that `path` is valid UTF-8.
+fn validate(path: &Path) -> Result<(), Utf8Error> {
+    std::str::from_utf8(path.as_os_str().as_encoded_bytes())
+        .map(|_| ())
+        .map_err(|e| Utf8Error {
+            path: path.to_path_buf(),
+            offset: e.valid_up_to(),
+        })
+}
+
+/// Converts a path that has already been validated into a `&str`.
+fn as_str(path: &Path) -> &str {
+    path.to_str().expect("path was validated as UTF-8")
+}
+
+/// A [`Dirname`] that is valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Utf8Dirname(Dirname);
+
+impl Utf8Dirname {
+    /// Returns this dirname as a `&str`.
+    pub fn as_str(&self) -> &str {
+        as_str(&self.0.name)
+    }
+}
+
+impl TryFrom<&str> for Utf8Dirname {
+    type Error = DirnameError;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the dirname is an absolute path or if no path was provided.
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        Dirname::try_from(path).map(Self)
+    }
+}
+
+impl TryFrom<Dirname> for Utf8Dirname {
+    type Error = Utf8Error;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the dirname is not valid UTF-8.
+    fn try_from(dir: Dirname) -> Result<Self, Self::Error> {
+        validate(&dir.name)?;
+        Ok(Self(dir))
+    }
+}
+
+impl From<Utf8Dirname> for Dirname {
+    fn from(dir: Utf8Dirname) -> Self {
+        dir.0
+    }
+}
+
+impl AsRef<DirnameRef> for Utf8Dirname {
+    fn as_ref(&self) -> &DirnameRef {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Utf8Dirname {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A [`Filename`] that is valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Utf8Filename(Filename);
+
+impl Utf8Filename {
+    /// Returns this filename as a `&str`.
+    pub fn as_str(&self) -> &str {
+        as_str(&self.0.name)
+    }
+}
+
+impl TryFrom<&str> for Utf8Filename {
+    type Error = FilenameError;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the filename is empty or has a root.
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        Filename::try_from(path).map(Self)
+    }
+}
+
+impl TryFrom<Filename> for Utf8Filename {
+    type Error = Utf8Error;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the filename is not valid UTF-8.
+    fn try_from(file: Filename) -> Result<Self, Self::Error> {
+        validate(&file.name)?;
+        Ok(Self(file))
+    }
+}
+
+impl From<Utf8Filename> for Filename {
+    fn from(file: Utf8Filename) -> Self {
+        file.0
+    }
+}
+
+impl AsRef<FilenameRef> for Utf8Filename {
+    fn as_ref(&self) -> &FilenameRef {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Utf8Filename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An [`AbsolutePath`] that is valid UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Utf8AbsolutePath(AbsolutePath);
+
+impl Utf8AbsolutePath {
+    /// Returns this path as a `&str`.
+    pub fn as_str(&self) -> &str {
+        as_str(&self.0 .0)
+    }
+
+    /// Returns this path as an [`AbsolutePath`].
+    pub fn as_absolute_path(&self) -> &AbsolutePath {
+        &self.0
+    }
+
+    /// Returns a new absolute path with the given directory appended.
+    #[must_use]
+    pub fn join(&self, dir: &Utf8Dirname) -> Utf8AbsolutePath {
+        Self(self.0.join(dir))
+    }
+
+    /// Returns a new absolute path with the given file appended.
+    #[must_use]
+    pub fn join_file(&self, file: &Utf8Filename) -> Utf8AbsolutePath {
+        Self(self.0.join_file(file))
+    }
+}
+
+impl TryFrom<&str> for Utf8AbsolutePath {
+    type Error = AbsolutePathError;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path is not absolute.
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        AbsolutePath::try_from(path).map(Self)
+    }
+}
+
+impl TryFrom<AbsolutePath> for Utf8AbsolutePath {
+    type Error = Utf8Error;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path is not valid UTF-8.
+    fn try_from(path: AbsolutePath) -> Result<Self, Self::Error> {
+        validate(&path.0)?;
+        Ok(Self(path))
+    }
+}
+
+impl From<Utf8AbsolutePath> for AbsolutePath {
+    fn from(path: Utf8AbsolutePath) -> Self {
+        path.0
+    }
+}
+
+impl AsRef<Path> for Utf8AbsolutePath {
+    fn as_ref(&self) -> &Path {
+        self.0.as_path()
+    }
+}
+
+impl AsRef<str> for Utf8AbsolutePath {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Display for Utf8AbsolutePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A [`VirtualPath`] whose base and relative part are valid UTF-8.
+///
+/// ```
+/// use vpath::{Utf8AbsolutePath, Utf8VirtualPath};
+///
+/// let output = Utf8AbsolutePath::try_from("/home/blog/output").unwrap();
+/// let post = Utf8VirtualPath::default()
+///     .with_dir_str("posts")
+///     .with_file_str("first.md")
+///     .with_extension("html")
+///     .with_base(&output);
+///
+/// assert_eq!(post.as_str(), "posts/first.html");
+/// assert_eq!(post.extension(), Some("html"));
+/// assert_eq!(post.to_string(), "/home/blog/output/posts/first.html");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Utf8VirtualPath<M: PathKind>(VirtualPath<M>);
+
+impl<M: PathKind> Utf8VirtualPath<M> {
+    /// Returns the relative part of this path as a `&str`.
+    pub fn as_str(&self) -> &str {
+        as_str(&self.0.path)
+    }
+
+    /// Returns the base of this path as a `&str`, if there is one.
+    pub fn base_str(&self) -> Option<&str> {
+        self.0.has_base().then(|| as_str(&self.0.base))
+    }
+
+    /// Returns this path as a [`VirtualPath`].
+    pub fn as_virtual_path(&self) -> &VirtualPath<M> {
+        &self.0
+    }
+
+    /// Generate a new `String` from the current virtual path, including the base.
+    pub fn to_full_string(&self) -> String {
+        as_str(&self.0.to_path_buf()).to_string()
+    }
+
+    /// Changes the "base" of this virtual path.
+    #[must_use]
+    pub fn with_base(&self, base: &Utf8AbsolutePath) -> Utf8VirtualPath<M> {
+        Self(self.0.with_base(&base.0))
+    }
+
+    /// Returns `true` if this path has a base.
+    pub fn has_base(&self) -> bool {
+        self.0.has_base()
+    }
+
+    /// Returns the path without its final component, if there is one.
+    pub fn parent(&self) -> Option<Utf8VirtualPath<DirMarker>> {
+        self.0.parent().map(Utf8VirtualPath)
+    }
+}
+
+impl Utf8VirtualPath<DirMarker> {
+    /// Push another directory onto this path.
+    pub fn push_dir(&mut self, dir: &Utf8Dirname) {
+        self.0.push_dir(dir);
+    }
+
+    /// Return this virtual path with the given directory pushed onto it.
+    ///
+    /// # Notes
+    ///
+    /// No checks are performed to confirm whether the `dir` is absolute or not.
+    pub fn with_dir_str(self, dir: &str) -> Self {
+        Self(self.0.with_dir_raw(dir))
+    }
+
+    /// Return this virtual path with the given directory pushed onto it.
+    pub fn with_dir(self, dir: &Utf8Dirname) -> Self {
+        Self(self.0.with_dir(dir))
+    }
+
+    /// Return this virtual path with the given file pushed onto it.
+    ///
+    /// # Notes
+    ///
+    /// No checks are performed to confirm whether the `file` is a file path or has any directory
+    /// components.
+    pub fn with_file_str(self, file: &str) -> Utf8VirtualPath<FileMarker> {
+        Utf8VirtualPath(self.0.with_file_raw(file))
+    }
+
+    /// Return this virtual path with the given file pushed onto it.
+    pub fn with_file(self, file: &Utf8Filename) -> Utf8VirtualPath<FileMarker> {
+        Utf8VirtualPath(self.0.with_file(file))
+    }
+}
+
+impl Utf8VirtualPath<FileMarker> {
+    /// Returns the name of this file without any parent directories.
+    ///
+    /// Returns `None` if the relative part is empty or ends in `..`.
+    pub fn file_name(&self) -> Option<&str> {
+        self.0.path.file_name().map(os_str_as_str)
+    }
+
+    /// Returns the extension of this file, if any.
+    pub fn extension(&self) -> Option<&str> {
+        self.0.extension().map(os_str_as_str)
+    }
+
+    /// Returns the file stem.
+    ///
+    /// Returns an empty string if the path has no [`file_name`](Self::file_name).
+    pub fn file_stem(&self) -> &str {
+        os_str_as_str(self.0.file_stem())
+    }
+
+    /// Returns the file name up to the first `.`, ignoring leading dots.
+    pub fn true_stem(&self) -> Option<&str> {
+        self.0.true_stem().map(os_str_as_str)
+    }
+
+    /// Returns every extension of this file joined by `.`, if any.
+    pub fn full_extension(&self) -> Option<&str> {
+        self.0.full_extension().map(os_str_as_str)
+    }
+
+    /// Returns an iterator over each extension of this file.
+    pub fn extensions(&self) -> impl Iterator<Item = &str> {
+        self.0.extensions().map(os_str_as_str)
+    }
+
+    /// Sets the extension for this file path.
+    pub fn set_extension(&mut self, extension: &str) {
+        self.0.set_extension(extension);
+    }
+
+    /// Sets the name for this file path.
+    pub fn set_file_name(&mut self, file_name: &str) {
+        self.0.set_file_name(file_name);
+    }
+
+    /// Returns this file path with an updated `extension`.
+    pub fn with_extension(self, extension: &str) -> Self {
+        Self(self.0.with_extension(extension))
+    }
+}
+
+fn os_str_as_str(os_str: &OsStr) -> &str {
+    os_str.to_str().expect("path was validated as UTF-8")
+}
+
+impl Default for Utf8VirtualPath<DirMarker> {
+    fn default() -> Self {
+        Self(VirtualPath::default())
+    }
+}
+
+impl<M: PathKind> TryFrom<VirtualPath<M>> for Utf8VirtualPath<M> {
+    type Error = Utf8Error;
+
+    /// # Errors
+    ///
+    /// An `Err` will be returned if either the base or the relative part is not valid UTF-8.
+    fn try_from(path: VirtualPath<M>) -> Result<Self, Self::Error> {
+        validate(&path.to_path_buf())?;
+        Ok(Self(path))
+    }
+}
+
+impl<M: PathKind> From<Utf8VirtualPath<M>> for VirtualPath<M> {
+    fn from(path: Utf8VirtualPath<M>) -> Self {
+        path.0
+    }
+}
+
+impl<M: PathKind> AsRef<VirtualPath<M>> for Utf8VirtualPath<M> {
+    fn as_ref(&self) -> &VirtualPath<M> {
+        &self.0
+    }
+}
+
+/// Displays the full path, including the base.
+impl<M: PathKind> std::fmt::Display for Utf8VirtualPath<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<M: PathKind> VirtualPath<M> {
+    /// Converts this path into a [`Utf8VirtualPath`].
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if either the base or the relative part is not valid UTF-8.
+    pub fn into_utf8(self) -> Result<Utf8VirtualPath<M>, Utf8Error> {
+        Utf8VirtualPath::try_from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_losslessly() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let path = VirtualPath::default()
+            .with_dir_raw("posts")
+            .with_file_raw("first.md")
+            .with_base(&base);
+
+        let utf8 = path.clone().into_utf8().unwrap();
+        assert_eq!(utf8.as_str(), "posts/first.md");
+        assert_eq!(utf8.base_str(), Some("/home"));
+        assert_eq!(utf8.file_stem(), "first");
+        assert_eq!(utf8.file_name(), Some("first.md"));
+        assert_eq!(VirtualPath::from(utf8), path);
+    }
+
+    #[test]
+    fn base_str_is_none_without_base() {
+        let path = Utf8VirtualPath::default().with_dir_str("posts");
+        assert_eq!(path.base_str(), None);
+    }
+
+    #[test]
+    fn converts_components() {
+        let dir = Utf8Dirname::try_from(Dirname::try_from("posts").unwrap()).unwrap();
+        let file = Utf8Filename::try_from("first.md").unwrap();
+        let abs = Utf8AbsolutePath::try_from("/home").unwrap();
+
+        assert_eq!(
+            abs.join(&dir).join_file(&file).as_str(),
+            "/home/posts/first.md"
+        );
+        assert_eq!(
+            Utf8VirtualPath::default()
+                .with_dir(&dir)
+                .with_file(&file)
+                .as_str(),
+            "posts/first.md"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reports_offset_of_invalid_byte() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = OsStr::from_bytes(b"ab\xffcd");
+        let path = VirtualPath::default()
+            .with_dir_raw("posts")
+            .with_file_raw(name);
+
+        let err = path.into_utf8().unwrap_err();
+        assert_eq!(err.offset(), 8);
+
+        let err = Utf8Filename::try_from(Filename::try_from(Path::new(name)).unwrap()).unwrap_err();
+        assert_eq!(err.offset(), 2);
+    }
+}
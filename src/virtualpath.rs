@@ -1,23 +1,29 @@
 use std::{
-    ffi::OsStr,
+    cmp::Ordering,
+    ffi::{OsStr, OsString},
+    hash::{Hash, Hasher},
     marker::PhantomData,
-    path::{PathBuf, StripPrefixError},
+    path::{self, Path, PathBuf},
 };
 
 use crate::absolute::AbsolutePath;
-use crate::dirname::Dirname;
-use crate::filename::Filename;
-use crate::marker::{DirMarker, FileMarker};
+use crate::component::Component;
+use crate::dirname::{Dirname, DirnameRef};
+use crate::error::{Error, Result};
+use crate::extension::{iter_extensions, split_extensions};
+use crate::filename::{Filename, FilenameError, FilenameRef};
+use crate::marker::{AnyMarker, DirMarker, File, FileKind, FileMarker, MarkerKind, PathKind};
+use crate::virtualpathref::VirtualPathRef;
 
 /// Generates paths with a "base" that can be switched.
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct VirtualPath<M> {
-    base: PathBuf,
-    path: PathBuf,
-    _phantom: PhantomData<M>,
+#[derive(Clone, Debug)]
+pub struct VirtualPath<M: PathKind> {
+    pub(crate) base: PathBuf,
+    pub(crate) path: PathBuf,
+    pub(crate) _phantom: PhantomData<M>,
 }
 
-impl<M> VirtualPath<M> {
+impl<M: PathKind> VirtualPath<M> {
     /// Generate a new `PathBuf` from the current virtual path.
     ///
     /// # Notes
@@ -39,14 +45,44 @@ impl<M> VirtualPath<M> {
         }
     }
 
+    /// Changes the "base" of this virtual path, reusing the existing relative path.
+    #[must_use]
+    pub fn into_base(self, base: &AbsolutePath) -> VirtualPath<M> {
+        VirtualPath {
+            base: base.0.clone(),
+            path: self.path,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Borrows the relative part of this path, without the base.
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use vpath::{AbsolutePath, VirtualPath};
+    ///
+    /// let output = AbsolutePath::try_from("/home/blog/output").unwrap();
+    /// let post = VirtualPath::default().with_file_raw("posts/first.html").with_base(&output);
+    /// assert_eq!(post.relative().as_path(), Path::new("posts/first.html"));
+    /// ```
+    pub fn relative(&self) -> &VirtualPathRef<M> {
+        VirtualPathRef::new(&self.path)
+    }
+
     /// Returns `true` if this path has a base.
     pub fn has_base(&self) -> bool {
         self.base.components().count() > 0
     }
 
     /// Returns `Ok(true)` if the path points at an existing entity.
-    pub fn try_exists(&self) -> std::io::Result<bool> {
-        self.to_path_buf().try_exists()
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the existence of the path cannot be determined.
+    pub fn try_exists(&self) -> Result<bool> {
+        self.to_path_buf()
+            .try_exists()
+            .map_err(|e| Error::io("check existence of", self, e))
     }
 
     /// Returns `true` if the path points at an existing entity.
@@ -55,19 +91,37 @@ impl<M> VirtualPath<M> {
     }
 
     /// Returns the canonical, absolute form of the path with all intermediate components normalized and symbolic links resolved.
-    pub fn canonicalize(&self) -> std::io::Result<PathBuf> {
-        self.to_path_buf().canonicalize()
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the path does not exist or cannot be resolved.
+    pub fn canonicalize(&self) -> Result<PathBuf> {
+        self.to_path_buf()
+            .canonicalize()
+            .map_err(|e| Error::io("canonicalize", self, e))
     }
 
     /// Removes the given prefix from the current virtual path.
     ///
     /// The prefix is only removed from the file/directory path component. The "base" path is not
     /// impacted.
-    pub fn strip_prefix<P>(self, prefix: P) -> Result<Self, StripPrefixError>
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if `prefix` is not a prefix of the virtual path.
+    pub fn strip_prefix<P>(self, prefix: P) -> Result<Self>
     where
         P: Into<PathBuf>,
     {
-        let stripped = self.path.strip_prefix(prefix.into())?;
+        let prefix = prefix.into();
+        let stripped = self
+            .path
+            .strip_prefix(&prefix)
+            .map_err(|source| Error::StripPrefix {
+                path: self.path.clone(),
+                prefix,
+                source,
+            })?;
         Ok(Self {
             base: self.base,
             path: stripped.to_path_buf(),
@@ -75,11 +129,45 @@ impl<M> VirtualPath<M> {
         })
     }
 
-    // Returns the path without its final component, if there is one.
-    pub fn parent(&self) -> Option<PathBuf> {
-        self.to_path_buf()
-            .parent()
-            .map(|parent| parent.to_path_buf())
+    /// Returns the path without its final component, if there is one.
+    ///
+    /// The base is never removed: `None` is returned once only the base remains.
+    pub fn parent(&self) -> Option<VirtualPath<DirMarker>> {
+        self.path.parent().map(|parent| self.with_relative(parent))
+    }
+
+    /// Returns an iterator over the parents of this path, ending with the empty path at the base.
+    ///
+    /// # Notes
+    ///
+    /// Unlike [`Path::ancestors`], the path itself is not included.
+    pub fn ancestors(&self) -> impl Iterator<Item = VirtualPath<DirMarker>> + '_ {
+        self.path
+            .ancestors()
+            .skip(1)
+            .map(|ancestor| self.with_relative(ancestor))
+    }
+
+    /// Returns the kind of entity identified by this path's marker.
+    pub fn kind(&self) -> MarkerKind {
+        M::KIND
+    }
+
+    /// Erases the marker, allowing directories and files to be stored together.
+    pub fn into_any(self) -> VirtualPath<AnyMarker> {
+        VirtualPath {
+            base: self.base,
+            path: self.path,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn with_relative<N: PathKind>(&self, path: &Path) -> VirtualPath<N> {
+        VirtualPath {
+            base: self.base.clone(),
+            path: path.to_path_buf(),
+            _phantom: PhantomData,
+        }
     }
 }
 
@@ -93,8 +181,11 @@ impl VirtualPath<DirMarker> {
     }
 
     /// Push another directory onto this path.
-    pub fn push_dir(&mut self, dir: Dirname) {
-        self.path.push(dir.name);
+    pub fn push_dir<D>(&mut self, dir: D)
+    where
+        D: AsRef<DirnameRef>,
+    {
+        self.path.push(dir.as_ref().as_path());
     }
 
     /// Return this virtual path with the given directory pushed onto it.
@@ -119,8 +210,11 @@ impl VirtualPath<DirMarker> {
     }
 
     /// Return this virtual path with the given directory pushed onto it.
-    pub fn with_dir(self, dir: Dirname) -> Self {
-        self.with_dir_raw(dir.name)
+    pub fn with_dir<D>(self, dir: D) -> Self
+    where
+        D: AsRef<DirnameRef>,
+    {
+        self.with_dir_raw(dir.as_ref().as_path())
     }
 
     /// Return this virtual path with the given file pushed onto it.
@@ -145,22 +239,313 @@ impl VirtualPath<DirMarker> {
     }
 
     /// Return this virtual path with the given file pushed onto it.
-    pub fn with_file(self, file: Filename) -> VirtualPath<FileMarker> {
-        self.with_file_raw(file.name)
+    pub fn with_file<F>(self, file: F) -> VirtualPath<FileMarker>
+    where
+        F: AsRef<FilenameRef>,
+    {
+        self.with_file_raw(file.as_ref().as_path())
+    }
+
+    /// Removes the last directory from this path.
+    ///
+    /// Returns `false` and does nothing if only the base remains.
+    pub fn pop(&mut self) -> bool {
+        self.path.pop()
+    }
+
+    /// Returns an iterator over the directories after the base.
+    pub fn components(&self) -> impl Iterator<Item = Component> + '_ {
+        dir_components(&self.path).map(Component::Dir)
     }
 }
 
-impl VirtualPath<FileMarker> {
+impl<K> VirtualPath<File<K>> {
     /// Returns the extension of this file, if any.
     pub fn extension(&self) -> Option<&OsStr> {
         self.path.extension()
     }
 
+    /// Returns the name of this file without any parent directories.
+    ///
+    /// The [`File`] marker does not guarantee a name: [`with_file_raw`](Self::with_file_raw)
+    /// does not check its input and [`AbsolutePath::strip_prefix`] may strip the entire relative
+    /// part. Returns `None` if the relative part is empty or ends in `..`.
+    pub fn file_name(&self) -> Option<Filename> {
+        self.raw_file_name().map(|name| Filename {
+            name: PathBuf::from(name),
+        })
+    }
+
+    /// Returns an iterator over the directories after the base, followed by the file.
+    ///
+    /// The file is omitted if the path has no [`file_name`](Self::file_name).
+    pub fn components(&self) -> impl Iterator<Item = Component> + '_ {
+        let dirs = match self.path.file_name() {
+            Some(_) => self.path.parent().unwrap_or(Path::new("")),
+            None => &self.path,
+        };
+        dir_components(dirs)
+            .map(Component::Dir)
+            .chain(self.file_name().map(Component::File))
+    }
+
     /// Returns the file stem.
+    ///
+    /// Returns an empty string if the path has no [`file_name`](Self::file_name).
     pub fn file_stem(&self) -> &OsStr {
-        self.path.file_stem().unwrap()
+        self.path.file_stem().unwrap_or_default()
+    }
+
+    /// Returns this file path with an updated `extension`.
+    ///
+    /// The file kind is reset to [`AnyFile`](crate::AnyFile) because the extension may no longer
+    /// match it. Use [`with_kind`](Self::with_kind) to keep a typed kind.
+    pub fn with_extension<S: AsRef<OsStr>>(self, extension: S) -> VirtualPath<FileMarker> {
+        let mut path = self.into_any_kind();
+        path.set_extension(extension);
+        path
+    }
+
+    fn raw_file_name(&self) -> Option<&OsStr> {
+        self.path.file_name()
+    }
+
+    /// Returns the file name up to the first `.`, ignoring leading dots.
+    ///
+    /// Unlike [`file_stem`](Self::file_stem), the stem of `archive.tar.gz` is `archive`. Returns
+    /// `None` if the path has no [`file_name`](Self::file_name).
+    pub fn true_stem(&self) -> Option<&OsStr> {
+        self.raw_file_name().map(|name| split_extensions(name).0)
+    }
+
+    /// Returns every extension of this file joined by `.`, if any.
+    ///
+    /// The full extension of `archive.tar.gz` is `tar.gz`.
+    pub fn full_extension(&self) -> Option<&OsStr> {
+        split_extensions(self.raw_file_name()?).1
+    }
+
+    /// Returns an iterator over each extension of this file.
+    ///
+    /// ```
+    /// use vpath::VirtualPath;
+    ///
+    /// let path = VirtualPath::default().with_file_raw("app.min.js");
+    /// let extensions: Vec<_> = path.extensions().collect();
+    /// assert_eq!(extensions, ["min", "js"]);
+    /// ```
+    pub fn extensions(&self) -> impl Iterator<Item = &OsStr> {
+        self.full_extension().into_iter().flat_map(iter_extensions)
     }
 
+    /// Returns this file path with every extension replaced.
+    ///
+    /// The file kind is reset to [`AnyFile`](crate::AnyFile).
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::VirtualPath;
+    ///
+    /// let path = VirtualPath::default()
+    ///     .with_file_raw("archive.tar.gz")
+    ///     .with_extensions(["tar", "zst"]);
+    /// assert_eq!(path.to_path_buf(), PathBuf::from("archive.tar.zst"));
+    /// ```
+    pub fn with_extensions<I, S>(self, extensions: I) -> VirtualPath<FileMarker>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut path = self.into_any_kind();
+        path.set_extensions(extensions);
+        path
+    }
+
+    /// Returns this file path with an extension appended after any existing extensions.
+    ///
+    /// The file kind is reset to [`AnyFile`](crate::AnyFile).
+    pub fn with_pushed_extension<S: AsRef<OsStr>>(self, extension: S) -> VirtualPath<FileMarker> {
+        let mut path = self.into_any_kind();
+        path.push_extension(extension);
+        path
+    }
+
+    /// Returns a path to `name` in the same directory as this file.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if `name` is not a single name, such as `../logo.png`.
+    pub fn sibling<F>(&self, name: F) -> Result<VirtualPath<FileMarker>>
+    where
+        F: AsRef<FilenameRef>,
+    {
+        self.checked_sibling(name.as_ref().as_path().as_os_str().to_os_string())
+    }
+
+    /// Returns a path in the same directory as this file with `suffix` inserted before the last
+    /// extension.
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::VirtualPath;
+    ///
+    /// let logo = VirtualPath::default().with_file_raw("img/logo.png");
+    /// let retina = logo.sibling_with_stem_suffix("@2x").unwrap();
+    /// assert_eq!(retina.to_path_buf(), PathBuf::from("img/logo@2x.png"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if this path has no file name or the resulting file name is not
+    /// a single valid name.
+    pub fn sibling_with_stem_suffix<S: AsRef<OsStr>>(&self, suffix: S) -> Result<Self> {
+        let mut name = self
+            .path
+            .file_stem()
+            .ok_or_else(|| self.no_file_name())?
+            .to_os_string();
+        name.push(suffix);
+        if let Some(extension) = self.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        self.checked_sibling(name)
+    }
+
+    /// Returns a path in the same directory as this file with `prefix` inserted before the file
+    /// name.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if this path has no file name or the resulting file name is not
+    /// a single valid name.
+    pub fn sibling_with_stem_prefix<S: AsRef<OsStr>>(&self, prefix: S) -> Result<Self> {
+        let mut name = prefix.as_ref().to_os_string();
+        name.push(self.raw_file_name().ok_or_else(|| self.no_file_name())?);
+        self.checked_sibling(name)
+    }
+
+    /// Returns a path in the same directory as this file with its last extension replaced by
+    /// `extension`.
+    ///
+    /// `extension` may contain multiple parts, so `post.md` can derive `post.meta.toml`. Only the
+    /// last extension is replaced, so files with dots in their stem derive distinct names.
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::VirtualPath;
+    ///
+    /// let post = VirtualPath::default().with_file_raw("posts/post.md");
+    /// let meta = post.derived("meta.toml").unwrap();
+    /// assert_eq!(meta.to_path_buf(), PathBuf::from("posts/post.meta.toml"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if this path has no file name or the resulting file name is not
+    /// a single valid name.
+    pub fn derived<S: AsRef<OsStr>>(&self, extension: S) -> Result<VirtualPath<FileMarker>> {
+        let mut name = self
+            .path
+            .file_stem()
+            .ok_or_else(|| self.no_file_name())?
+            .to_os_string();
+        name.push(".");
+        name.push(extension);
+        self.checked_sibling(name)
+    }
+
+    fn no_file_name(&self) -> Error {
+        Error::Filename {
+            input: Some(self.path.to_string_lossy().into_owned()),
+            source: FilenameError::Empty,
+        }
+    }
+
+    /// Returns a sibling path after confirming that `name` is a single file name.
+    fn checked_sibling<N: PathKind>(&self, name: OsString) -> Result<VirtualPath<N>> {
+        let input = || Some(name.to_string_lossy().into_owned());
+        let file = FilenameRef::new(&name).map_err(|source| Error::Filename {
+            input: input(),
+            source,
+        })?;
+
+        let mut components = file.as_path().components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(path::Component::Normal(_)), None)
+        ) {
+            return Err(Error::Filename {
+                input: input(),
+                source: FilenameError::NotSingleName,
+            });
+        }
+
+        Ok(self.with_file_name_unchecked(file.as_path()))
+    }
+
+    fn with_file_name_unchecked<N: PathKind>(&self, name: &Path) -> VirtualPath<N> {
+        let mut path = self.path.clone();
+        path.set_file_name(name);
+        VirtualPath {
+            base: self.base.clone(),
+            path,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns this path as a file of any kind.
+    pub fn into_any_kind(self) -> VirtualPath<FileMarker> {
+        self.into_kind_unchecked()
+    }
+
+    fn into_kind_unchecked<N>(self) -> VirtualPath<File<N>> {
+        VirtualPath {
+            base: self.base,
+            path: self.path,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Converts this path into a file of kind `N` if its extension matches the kind.
+    ///
+    /// ```
+    /// use vpath::{Html, Markdown, VirtualPath};
+    ///
+    /// let post = VirtualPath::default().with_file_raw("posts/first.md");
+    /// assert!(post.clone().try_into_kind::<Html>().is_err());
+    ///
+    /// let post = post.try_into_kind::<Markdown>().unwrap();
+    /// let html = post.with_kind::<Html>();
+    /// assert_eq!(html.to_string(), "posts/first.html");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The path is returned unchanged if the extension does not match.
+    pub fn try_into_kind<N: FileKind>(self) -> std::result::Result<VirtualPath<File<N>>, Self> {
+        if N::matches(self.extension()) {
+            Ok(self.into_kind_unchecked())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Returns this path as a file of kind `N`, replacing the extension with the primary
+    /// extension of `N`.
+    ///
+    /// The extension is left unchanged if `N` has no extensions, such as
+    /// [`AnyFile`](crate::AnyFile).
+    pub fn with_kind<N: FileKind>(self) -> VirtualPath<File<N>> {
+        let mut path = self.into_kind_unchecked();
+        if let Some(extension) = N::EXTENSIONS.first() {
+            path.path.set_extension(extension);
+        }
+        path
+    }
+}
+
+impl VirtualPath<FileMarker> {
     /// Sets the extension for this file path.
     pub fn set_extension<S: AsRef<OsStr>>(&mut self, extension: S) {
         self.path.set_extension(extension);
@@ -171,10 +556,159 @@ impl VirtualPath<FileMarker> {
         self.path.set_file_name(file_name)
     }
 
-    /// Returns this file path with an updated `extension`.
-    pub fn with_extension<S: AsRef<OsStr>>(mut self, extension: S) -> Self {
-        self.path.set_extension(extension);
-        self
+    /// Replaces every extension of this file.
+    ///
+    /// No extension remains if `extensions` is empty. Nothing is changed if the path has no
+    /// [`file_name`](VirtualPath::file_name).
+    pub fn set_extensions<I, S>(&mut self, extensions: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let Some(stem) = self.true_stem() else {
+            return;
+        };
+        let mut name = stem.to_os_string();
+        for extension in extensions {
+            name.push(".");
+            name.push(extension);
+        }
+        self.path.set_file_name(name);
+    }
+
+    /// Appends an extension after any existing extensions.
+    ///
+    /// Nothing is changed if the path has no [`file_name`](VirtualPath::file_name).
+    pub fn push_extension<S: AsRef<OsStr>>(&mut self, extension: S) {
+        let Some(name) = self.raw_file_name() else {
+            return;
+        };
+        let mut name = name.to_os_string();
+        name.push(".");
+        name.push(extension);
+        self.path.set_file_name(name);
+    }
+
+    /// Removes the last extension and returns it.
+    ///
+    /// Returns `None` and does nothing if the file has no extension.
+    pub fn pop_extension(&mut self) -> Option<OsString> {
+        let extension = self.extensions().last()?.to_os_string();
+        self.path.set_extension("");
+        Some(extension)
+    }
+}
+
+impl VirtualPath<AnyMarker> {
+    /// Converts into a file path after checking that the path points at an existing file.
+    ///
+    /// Symbolic links are followed. Use [`into_file`](Self::into_file) when the kind is already
+    /// known.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::KindMismatch`](crate::Error::KindMismatch) will be returned if the path is not
+    /// a file. Any other `Err` will be returned if the path does not exist or cannot be inspected.
+    pub fn try_into_file(self) -> Result<VirtualPath<FileMarker>> {
+        let file = self.into_file();
+        file.check_kind()?;
+        Ok(file)
+    }
+
+    /// Converts into a directory path after checking that the path points at an existing
+    /// directory.
+    ///
+    /// Symbolic links are followed. Use [`into_dir`](Self::into_dir) when the kind is already
+    /// known.
+    ///
+    /// # Errors
+    ///
+    /// An [`Error::KindMismatch`](crate::Error::KindMismatch) will be returned if the path is not
+    /// a directory. Any other `Err` will be returned if the path does not exist or cannot be
+    /// inspected.
+    pub fn try_into_dir(self) -> Result<VirtualPath<DirMarker>> {
+        let dir = self.into_dir();
+        dir.check_kind()?;
+        Ok(dir)
+    }
+
+    /// Converts into a file path without consulting the filesystem.
+    pub fn into_file(self) -> VirtualPath<FileMarker> {
+        self.into_marker()
+    }
+
+    /// Converts into a directory path without consulting the filesystem.
+    pub fn into_dir(self) -> VirtualPath<DirMarker> {
+        self.into_marker()
+    }
+
+    fn into_marker<N: PathKind>(self) -> VirtualPath<N> {
+        VirtualPath {
+            base: self.base,
+            path: self.path,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Converts each component of a relative path into a [`Dirname`].
+fn dir_components(path: &Path) -> impl Iterator<Item = Dirname> + '_ {
+    path.components()
+        .filter(|component| {
+            !matches!(
+                component,
+                path::Component::Prefix(_) | path::Component::RootDir
+            )
+        })
+        .map(|component| Dirname {
+            name: PathBuf::from(component.as_os_str()),
+        })
+}
+
+/// Borrows the relative part, like [`relative`](VirtualPath::relative).
+impl<M: PathKind> AsRef<VirtualPathRef<M>> for VirtualPath<M> {
+    fn as_ref(&self) -> &VirtualPathRef<M> {
+        self.relative()
+    }
+}
+
+impl<M: PathKind> Hash for VirtualPath<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.base.hash(state);
+        self.path.hash(state);
+    }
+}
+
+/// Paths are equal when both the base and the relative part are equal.
+///
+/// Use [`RelativeKey`](crate::RelativeKey) to compare only the relative part.
+impl<M: PathKind> PartialEq for VirtualPath<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base && self.path == other.path
+    }
+}
+
+impl<M: PathKind> Eq for VirtualPath<M> {}
+
+/// Paths are ordered by their relative part first, and then by their base.
+impl<M: PathKind> PartialOrd for VirtualPath<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M: PathKind> Ord for VirtualPath<M> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path
+            .cmp(&other.path)
+            .then_with(|| self.base.cmp(&other.base))
+    }
+}
+
+/// Displays the full path, including the base.
+impl<M: PathKind> std::fmt::Display for VirtualPath<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.to_path_buf().display(), f)
     }
 }
 
@@ -191,6 +725,7 @@ impl Default for VirtualPath<DirMarker> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::temp_tree;
 
     #[test]
     fn pushes_file_with_parent_dir() {
@@ -275,6 +810,25 @@ mod tests {
         assert_eq!(path.file_stem(), OsStr::new("index"));
     }
 
+    #[test]
+    fn file_without_a_name_does_not_panic() {
+        let base = AbsolutePath::try_from("/home/blog").unwrap();
+        let empty = base.strip_prefix(&base).unwrap().into_file();
+        let parent = VirtualPath::default().with_file_raw("posts/..");
+
+        for path in [empty, parent] {
+            assert!(path.file_name().is_none());
+            assert!(path.file_stem().is_empty());
+            assert!(path.true_stem().is_none());
+            assert!(path.full_extension().is_none());
+            assert!(path.derived("toml").is_err());
+            assert!(path.sibling_with_stem_prefix("_").is_err());
+            assert!(path
+                .components()
+                .all(|component| matches!(component, Component::Dir(_))));
+        }
+    }
+
     #[test]
     fn returns_file_extension() {
         let path = VirtualPath::default().with_file_raw("index.html");
@@ -322,4 +876,253 @@ mod tests {
 
         assert_eq!(path.to_path_buf(), PathBuf::from("b/c/test.html"));
     }
+
+    #[test]
+    fn parent_keeps_base_and_stops_at_base() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let path = VirtualPath::default()
+            .with_dir_raw("a")
+            .with_file_raw("test.html")
+            .with_base(&base);
+
+        let parent = path.parent().unwrap();
+        assert_eq!(parent.to_path_buf(), PathBuf::from("/home/a"));
+
+        let parent = parent.parent().unwrap();
+        assert_eq!(parent.to_path_buf(), PathBuf::from("/home"));
+        assert!(parent.has_base());
+
+        assert!(parent.parent().is_none());
+    }
+
+    #[test]
+    fn ancestors_end_at_base() {
+        let path = VirtualPath::default()
+            .with_dir_raw("a/b")
+            .with_file_raw("test.html");
+
+        let ancestors: Vec<_> = path.ancestors().map(|dir| dir.to_path_buf()).collect();
+        assert_eq!(
+            ancestors,
+            vec![PathBuf::from("a/b"), PathBuf::from("a"), PathBuf::from("")]
+        );
+    }
+
+    #[test]
+    fn returns_typed_components() {
+        let path = VirtualPath::default()
+            .with_dir_raw("a/b")
+            .with_file_raw("test.html");
+
+        let components: Vec<_> = path.components().collect();
+        assert_eq!(components.len(), 3);
+        assert!(matches!(&components[0], Component::Dir(dir) if dir.name == Path::new("a")));
+        assert!(matches!(&components[1], Component::Dir(dir) if dir.name == Path::new("b")));
+        assert!(
+            matches!(&components[2], Component::File(file) if file.name == Path::new("test.html"))
+        );
+
+        let dir = VirtualPath::default().with_dir_raw("a/b");
+        assert!(dir.components().all(|c| matches!(c, Component::Dir(_))));
+    }
+
+    #[test]
+    fn returns_depth() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let path = VirtualPath::default().with_dir_raw("a/b").with_base(&base);
+
+        assert_eq!(path.relative().depth(), 2);
+        assert_eq!(VirtualPath::default().relative().depth(), 0);
+    }
+
+    #[test]
+    fn pops_until_base() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let mut path = VirtualPath::default().with_dir_raw("a").with_base(&base);
+
+        assert!(path.pop());
+        assert_eq!(path.to_path_buf(), PathBuf::from("/home"));
+        assert!(!path.pop());
+        assert_eq!(path.to_path_buf(), PathBuf::from("/home"));
+    }
+
+    #[test]
+    fn returns_file_name_without_parents() {
+        let path =
+            VirtualPath::default().with_file(Filename::try_from("parent/index.html").unwrap());
+
+        assert_eq!(path.file_name().unwrap().name, PathBuf::from("index.html"));
+    }
+
+    #[test]
+    fn returns_multi_part_extensions() {
+        let path = VirtualPath::default().with_file_raw("dist/archive.tar.gz");
+
+        assert_eq!(path.true_stem(), Some(OsStr::new("archive")));
+        assert_eq!(path.full_extension(), Some(OsStr::new("tar.gz")));
+        assert_eq!(path.extensions().collect::<Vec<_>>(), ["tar", "gz"]);
+    }
+
+    #[test]
+    fn hidden_file_has_no_extensions() {
+        let path = VirtualPath::default().with_file_raw(".bashrc");
+
+        assert_eq!(path.true_stem(), Some(OsStr::new(".bashrc")));
+        assert_eq!(path.full_extension(), None);
+        assert_eq!(path.extensions().count(), 0);
+    }
+
+    #[test]
+    fn replaces_all_extensions() {
+        let path = VirtualPath::default()
+            .with_file_raw("app.min.js")
+            .with_extensions(["js"]);
+        assert_eq!(path.to_path_buf(), PathBuf::from("app.js"));
+
+        let path = path.with_extensions(Vec::<&str>::new());
+        assert_eq!(path.to_path_buf(), PathBuf::from("app"));
+    }
+
+    #[test]
+    fn pushes_and_pops_extensions() {
+        let mut path = VirtualPath::default().with_file_raw("logo.png");
+
+        path.push_extension("br");
+        assert_eq!(path.to_path_buf(), PathBuf::from("logo.png.br"));
+
+        assert_eq!(path.pop_extension(), Some(OsString::from("br")));
+        assert_eq!(path.pop_extension(), Some(OsString::from("png")));
+        assert_eq!(path.pop_extension(), None);
+        assert_eq!(path.to_path_buf(), PathBuf::from("logo"));
+    }
+
+    #[test]
+    fn creates_siblings() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let logo = VirtualPath::default()
+            .with_file_raw("img/logo.png")
+            .with_base(&base);
+
+        let webp = logo
+            .sibling(Filename::try_from("logo.webp").unwrap())
+            .unwrap();
+        assert_eq!(webp.to_path_buf(), PathBuf::from("/home/img/logo.webp"));
+
+        let prefixed = logo.sibling_with_stem_prefix("_").unwrap();
+        assert_eq!(prefixed.to_path_buf(), PathBuf::from("/home/img/_logo.png"));
+
+        let suffixed = logo.sibling_with_stem_suffix("-v2").unwrap();
+        assert_eq!(
+            suffixed.to_path_buf(),
+            PathBuf::from("/home/img/logo-v2.png")
+        );
+    }
+
+    #[test]
+    fn rejects_siblings_that_are_not_a_single_name() {
+        let logo = VirtualPath::default().with_file_raw("img/logo.png");
+
+        assert!(logo.sibling_with_stem_suffix("/x").is_err());
+        assert!(logo.sibling_with_stem_prefix("../").is_err());
+        assert!(logo.derived("a/b").is_err());
+        assert!(logo.sibling(Filename::try_from("../x").unwrap()).is_err());
+
+        let err = logo.sibling_with_stem_prefix("sub/").unwrap_err();
+        assert_eq!(err.input(), Some("sub/logo.png"));
+    }
+
+    #[test]
+    fn dotted_stems_keep_everything_before_the_last_extension() {
+        let file = |name| VirtualPath::default().with_file_raw(name);
+
+        let meta = |name| file(name).derived("meta.toml").unwrap().to_string();
+        assert_eq!(meta("post.md"), "post.meta.toml");
+        assert_eq!(meta("my.post.md"), "my.post.meta.toml");
+        assert_eq!(meta("2024.01.notes.md"), "2024.01.notes.meta.toml");
+        assert_ne!(meta("2024.01.notes.md"), meta("2024.02.notes.md"));
+
+        let retina = file("jquery.min.js")
+            .sibling_with_stem_suffix("@2x")
+            .unwrap();
+        assert_eq!(retina.to_string(), "jquery.min@2x.js");
+    }
+
+    #[test]
+    fn converts_between_file_kinds() {
+        use crate::marker::{Html, Markdown};
+
+        let post = VirtualPath::default().with_file_raw("posts/first.MD");
+        let post: VirtualPath<File<Markdown>> = post.try_into_kind().unwrap();
+        let html: VirtualPath<File<Html>> = post.clone().with_kind();
+        assert_eq!(html.to_path_buf(), PathBuf::from("posts/first.html"));
+
+        let pdf: VirtualPath<FileMarker> = post.with_extension("pdf");
+        assert!(pdf.try_into_kind::<Markdown>().is_err());
+    }
+
+    #[test]
+    fn typed_siblings_keep_kind() {
+        use crate::marker::Css;
+
+        let css = VirtualPath::default()
+            .with_file_raw("site.css")
+            .try_into_kind::<Css>()
+            .unwrap();
+        let min: VirtualPath<File<Css>> = css.sibling_with_stem_suffix("-min").unwrap();
+        assert_eq!(min.to_path_buf(), PathBuf::from("site-min.css"));
+    }
+
+    #[test]
+    fn displays_full_path() {
+        let path = VirtualPath::default()
+            .with_file_raw("index.html")
+            .with_base(&AbsolutePath::try_from("/home").unwrap());
+
+        assert_eq!(path.to_string(), "/home/index.html");
+    }
+
+    #[test]
+    fn orders_by_relative_part_then_base() {
+        let a = AbsolutePath::try_from("/a").unwrap();
+        let b = AbsolutePath::try_from("/b").unwrap();
+        let first = VirtualPath::default().with_file_raw("1.md");
+        let second = VirtualPath::default().with_file_raw("2.md");
+
+        assert!(first.with_base(&b) < second.with_base(&a));
+        assert!(first.with_base(&a) < first.with_base(&b));
+    }
+
+    #[test]
+    fn any_marker_converts_with_known_kind() {
+        let path = VirtualPath::default().with_file_raw("a/index.html");
+        assert_eq!(path.kind(), MarkerKind::File);
+
+        let any = path.into_any();
+        assert_eq!(any.kind(), MarkerKind::Any);
+
+        let file = any.clone().into_file();
+        assert_eq!(file.to_string(), "a/index.html");
+        assert_eq!(any.into_dir().kind(), MarkerKind::Dir);
+    }
+
+    #[test]
+    fn any_marker_converts_from_filesystem() {
+        let (_dir, base) = temp_tree(&[("src/", ""), ("Cargo.toml", "")]);
+        let root = VirtualPath::default().into_base(&base);
+
+        let src = root.clone().with_dir_raw("src").into_any();
+        let err = src.clone().try_into_file().unwrap_err();
+        assert!(matches!(err, crate::Error::KindMismatch { .. }));
+        assert!(src.try_into_dir().unwrap().exists());
+
+        let manifest = root.clone().with_file_raw("Cargo.toml").into_any();
+        assert!(manifest.try_into_file().is_ok());
+
+        let missing = root.with_file_raw("missing.txt").into_any();
+        let err = missing.try_into_file().unwrap_err();
+        assert_eq!(
+            err.io_error().map(std::io::Error::kind),
+            Some(std::io::ErrorKind::NotFound)
+        );
+    }
 }
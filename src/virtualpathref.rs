@@ -0,0 +1,227 @@
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use crate::absolute::AbsolutePath;
+use crate::filename::FilenameRef;
+use crate::marker::{File, PathKind};
+use crate::natural::natural_cmp;
+use crate::virtualpath::VirtualPath;
+
+/// A borrowed relative virtual path, without a base.
+///
+/// Functions that only look at the relative part of a path can accept `&VirtualPathRef<M>`
+/// without requiring an allocation. Borrow one from a [`VirtualPath`] with
+/// [`relative`](VirtualPath::relative) or [`AsRef`].
+///
+/// # Notes
+///
+/// Unlike [`Path`] and [`PathBuf`], this is not the borrowed form of [`VirtualPath`]: it has no
+/// base, while a `VirtualPath` is compared and hashed by its base too. So `VirtualPath` does not
+/// dereference to it, and it does not implement `Borrow` or `ToOwned` with `VirtualPath`. Key
+/// collections with [`RelativeKey`](crate::RelativeKey) to look them up by `&VirtualPathRef`.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use vpath::{AbsolutePath, FileMarker, RelativeKey, VirtualPath, VirtualPathRef};
+///
+/// let output = AbsolutePath::try_from("/home/blog/output").unwrap();
+/// let mut titles = HashMap::new();
+/// titles.insert(
+///     RelativeKey(VirtualPath::default().with_file_raw("posts/first.html").with_base(&output)),
+///     "First",
+/// );
+///
+/// let key: &VirtualPathRef<FileMarker> = VirtualPathRef::new("posts/first.html");
+/// assert_eq!(titles.get(key), Some(&"First"));
+/// ```
+#[repr(transparent)]
+pub struct VirtualPathRef<M: PathKind> {
+    _phantom: PhantomData<M>,
+    path: Path,
+}
+
+impl<M: PathKind> VirtualPathRef<M> {
+    /// Borrows `path` as a virtual path.
+    ///
+    /// # Notes
+    ///
+    /// No checks are performed to confirm whether `path` is relative or matches the marker.
+    pub fn new<P>(path: &P) -> &VirtualPathRef<M>
+    where
+        P: AsRef<Path> + ?Sized,
+    {
+        let path = path.as_ref();
+        // SAFETY: `VirtualPathRef` is `repr(transparent)` over `Path`.
+        unsafe { &*(path as *const Path as *const VirtualPathRef<M>) }
+    }
+
+    /// Returns the relative part of this path.
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of components in this path.
+    pub fn depth(&self) -> usize {
+        self.path.components().count()
+    }
+
+    /// Returns `true` if `prefix` is a prefix of this path.
+    ///
+    /// Only whole path components are matched.
+    pub fn starts_with<P>(&self, prefix: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.path.starts_with(prefix)
+    }
+
+    /// Compares two paths using a natural sort order, so that `file2` is ordered before `file10`.
+    ///
+    /// ```
+    /// use vpath::{FileMarker, VirtualPath};
+    ///
+    /// let mut posts: Vec<VirtualPath<FileMarker>> = ["post10.md", "post2.md", "post1.md"]
+    ///     .into_iter()
+    ///     .map(|name| VirtualPath::default().with_file_raw(name))
+    ///     .collect();
+    /// posts.sort_by(|a, b| a.relative().natural_cmp(b.relative()));
+    ///
+    /// let names: Vec<_> = posts.iter().map(|post| post.to_string()).collect();
+    /// assert_eq!(names, ["post1.md", "post2.md", "post10.md"]);
+    /// ```
+    pub fn natural_cmp(&self, other: &VirtualPathRef<M>) -> Ordering {
+        natural_cmp(&self.path, &other.path)
+    }
+
+    /// Returns an owned virtual path without a base.
+    pub fn to_virtual_path(&self) -> VirtualPath<M> {
+        VirtualPath {
+            base: PathBuf::default(),
+            path: self.path.to_path_buf(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an owned virtual path with the given base.
+    pub fn to_virtual_path_with_base(&self, base: &AbsolutePath) -> VirtualPath<M> {
+        VirtualPath {
+            base: base.0.clone(),
+            path: self.path.to_path_buf(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<K> VirtualPathRef<File<K>> {
+    /// Returns the name of this file without any parent directories.
+    ///
+    /// Returns `None` if the path is empty or ends in `..`.
+    pub fn file_name(&self) -> Option<&FilenameRef> {
+        let name = self.path.file_name()?;
+        Some(FilenameRef::from_path_unchecked(Path::new(name)))
+    }
+
+    /// Returns the extension of this file, if any.
+    pub fn extension(&self) -> Option<&OsStr> {
+        self.path.extension()
+    }
+}
+
+impl<M: PathKind> AsRef<Path> for VirtualPathRef<M> {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<M: PathKind> AsRef<VirtualPathRef<M>> for VirtualPathRef<M> {
+    fn as_ref(&self) -> &VirtualPathRef<M> {
+        self
+    }
+}
+
+impl<M: PathKind> PartialEq for VirtualPathRef<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl<M: PathKind> Eq for VirtualPathRef<M> {}
+
+impl<M: PathKind> Hash for VirtualPathRef<M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+    }
+}
+
+impl<M: PathKind> PartialOrd for VirtualPathRef<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M: PathKind> Ord for VirtualPathRef<M> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.path.cmp(&other.path)
+    }
+}
+
+impl<M: PathKind> std::fmt::Display for VirtualPathRef<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.path.display(), f)
+    }
+}
+
+impl<M: PathKind> std::fmt::Debug for VirtualPathRef<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("VirtualPathRef").field(&&self.path).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::marker::{DirMarker, FileMarker};
+    use crate::relativekey::RelativeKey;
+
+    #[test]
+    fn borrows_relative_part() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let path = VirtualPath::default()
+            .with_dir_raw("a/b")
+            .with_file_raw("c.md")
+            .with_base(&base);
+
+        let borrowed: &VirtualPathRef<FileMarker> = path.relative();
+        assert_eq!(borrowed.as_path(), Path::new("a/b/c.md"));
+        assert_eq!(borrowed.depth(), 3);
+        assert_eq!(borrowed.file_name().unwrap().as_path(), Path::new("c.md"));
+    }
+
+    #[test]
+    fn to_virtual_path_has_no_base() {
+        let borrowed: &VirtualPathRef<DirMarker> = VirtualPathRef::new("a/b");
+        let owned = borrowed.to_virtual_path();
+
+        assert!(!owned.has_base());
+        assert_eq!(owned.to_path_buf(), PathBuf::from("a/b"));
+    }
+
+    #[test]
+    fn looks_up_relative_key_with_borrowed_key() {
+        let base = AbsolutePath::try_from("/home").unwrap();
+        let mut set = HashSet::new();
+        set.insert(RelativeKey(
+            VirtualPath::default().with_dir_raw("a").with_base(&base),
+        ));
+
+        assert!(set.contains(VirtualPathRef::<DirMarker>::new("a")));
+        assert!(!set.contains(VirtualPathRef::<DirMarker>::new("b")));
+    }
+}
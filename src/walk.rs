@@ -0,0 +1,536 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::glob::Glob;
+use crate::ignore::{IgnoreRules, IgnoreStack};
+use crate::marker::{AnyMarker, DirMarker, FileMarker};
+use crate::virtualpath::VirtualPath;
+
+impl VirtualPath<DirMarker> {
+    /// Returns an iterator over every file under this directory.
+    ///
+    /// Files keep the base of this directory. Directories are visited depth-first and the
+    /// entries of each directory are visited in order of their names, so the order is the same on
+    /// every platform. Symbolic links are followed unless disabled with [`Walk::follow_links`].
+    ///
+    /// An entry that cannot be inspected, such as a broken link or a link to one of its own parent
+    /// directories, is yielded as an `Err` and the rest of the walk continues.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # std::fs::create_dir_all(dir.path().join("static/css")).unwrap();
+    /// # std::fs::write(dir.path().join("static/css/site.css"), "").unwrap();
+    /// # std::fs::write(dir.path().join("static/robots.txt"), "").unwrap();
+    /// use vpath::{AbsolutePath, VirtualPath};
+    ///
+    /// let source = AbsolutePath::try_from(dir.path()).unwrap();
+    /// let static_dir = VirtualPath::default().with_dir_raw("static").with_base(&source);
+    ///
+    /// let files: Vec<String> = static_dir
+    ///     .walk()
+    ///     .map(|file| file.unwrap().relative().as_path().display().to_string())
+    ///     .collect();
+    /// assert_eq!(files, vec!["static/css/site.css", "static/robots.txt"]);
+    /// ```
+    pub fn walk(&self) -> Walk {
+        Walk {
+            root: self.path.clone(),
+            stack: vec![Pending::Dir(PendingDir {
+                dir: self.clone(),
+                ignores: None,
+                ancestors: None,
+            })],
+            follow_links: true,
+            git_ignore: false,
+            ignore_file_names: Vec::new(),
+            ignore_globs: Vec::new(),
+        }
+    }
+}
+
+/// An iterator over the files under a directory.
+///
+/// This is created by [`VirtualPath::walk`].
+///
+/// Ignore files are disabled by default. Once enabled, an ignore file applies to its own
+/// directory and everything below it, using `.gitignore` syntax. Rules in deeper ignore files
+/// take precedence, and ignored directories are not entered. Ignore files above the directory
+/// being walked are not read.
+///
+/// ```
+/// # let dir = tempfile::tempdir().unwrap();
+/// # std::fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+/// # std::fs::write(dir.path().join("node_modules/pkg/index.js"), "").unwrap();
+/// # std::fs::write(dir.path().join("index.md"), "").unwrap();
+/// # std::fs::write(dir.path().join("index.md.swp"), "").unwrap();
+/// # std::fs::write(dir.path().join(".gitignore"), "node_modules/\n").unwrap();
+/// use vpath::{AbsolutePath, Glob, VirtualPath};
+///
+/// let source = AbsolutePath::try_from(dir.path()).unwrap();
+/// let files: Vec<String> = VirtualPath::default()
+///     .with_base(&source)
+///     .walk()
+///     .git_ignore(true)
+///     .ignore_glob(Glob::new("**/*.swp").unwrap())
+///     .map(|file| file.unwrap().relative().as_path().display().to_string())
+///     .collect();
+/// assert_eq!(files, vec![".gitignore", "index.md"]);
+/// ```
+#[derive(Debug)]
+pub struct Walk {
+    root: PathBuf,
+    pub(crate) stack: Vec<Pending>,
+    follow_links: bool,
+    git_ignore: bool,
+    ignore_file_names: Vec<OsString>,
+    ignore_globs: Vec<Glob>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Pending {
+    Dir(PendingDir),
+    File(VirtualPath<FileMarker>),
+    /// An entry that could not be inspected
+    Error(Error),
+}
+
+/// A directory that has not been read yet.
+#[derive(Debug)]
+pub(crate) struct PendingDir {
+    dir: VirtualPath<DirMarker>,
+    ignores: Option<Arc<IgnoreStack>>,
+    /// This directory and the ones above it, tracked only when links are followed
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+/// A directory being read or one above it, used to detect links that form a loop.
+#[derive(Debug)]
+struct Ancestor {
+    id: DirId,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl Ancestor {
+    fn contains(mut ancestor: Option<&Ancestor>, id: &DirId) -> bool {
+        while let Some(current) = ancestor {
+            if current.id == *id {
+                return true;
+            }
+            ancestor = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+#[cfg(unix)]
+type DirId = (u64, u64);
+
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// Identifies the directory at `path` after following links.
+#[cfg(unix)]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies the directory at `path` after following links.
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+    fs::canonicalize(path)
+}
+
+impl Walk {
+    /// Sets whether symbolic links are followed. Defaults to `true`.
+    ///
+    /// When links are not followed, every link is yielded as a file, including links to
+    /// directories. When they are followed, a link to a directory that contains it is reported
+    /// as an error instead of being walked again.
+    #[must_use]
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.follow_links = follow;
+        self
+    }
+
+    /// Sets whether `.gitignore` files are honored. Defaults to `false`.
+    ///
+    /// When enabled, `.git` directories are also skipped.
+    #[must_use]
+    pub fn git_ignore(mut self, enabled: bool) -> Self {
+        self.git_ignore = enabled;
+        self
+    }
+
+    /// Sets whether `.ignore` files are honored. Defaults to `false`.
+    ///
+    /// Rules in `.ignore` take precedence over rules in `.gitignore` in the same directory.
+    #[must_use]
+    pub fn ignore(self, enabled: bool) -> Self {
+        if enabled {
+            self.ignore_file_name(".ignore")
+        } else {
+            self.without_ignore_file_name(".ignore")
+        }
+    }
+
+    /// Honors ignore files with the given name.
+    ///
+    /// Rules in files added later take precedence over rules in files added earlier and in
+    /// `.gitignore`, when they are in the same directory.
+    #[must_use]
+    pub fn ignore_file_name<S: AsRef<OsStr>>(mut self, name: S) -> Self {
+        let name = name.as_ref().to_os_string();
+        if !self.ignore_file_names.contains(&name) {
+            self.ignore_file_names.push(name);
+        }
+        self
+    }
+
+    /// Ignores files and directories matching `glob`, relative to the directory being walked.
+    ///
+    /// These globs apply regardless of any ignore files.
+    #[must_use]
+    pub fn ignore_glob(mut self, glob: Glob) -> Self {
+        self.ignore_globs.push(glob);
+        self
+    }
+
+    fn without_ignore_file_name(mut self, name: &str) -> Self {
+        self.ignore_file_names.retain(|existing| existing != name);
+        self
+    }
+
+    /// Reads the ignore files in `dir` and adds their rules on top of `parent`.
+    ///
+    /// An ignore file that cannot be read is skipped and reported in `errors`.
+    fn read_ignore_files(
+        &self,
+        dir: &VirtualPath<DirMarker>,
+        parent: Option<Arc<IgnoreStack>>,
+        errors: &mut Vec<Pending>,
+    ) -> Option<Arc<IgnoreStack>> {
+        let git_ignore = self.git_ignore.then_some(OsStr::new(".gitignore"));
+        let names = git_ignore
+            .into_iter()
+            .chain(self.ignore_file_names.iter().map(OsString::as_os_str));
+
+        let relative = dir.path.strip_prefix(&self.root).unwrap_or(&dir.path);
+        let mut rules = IgnoreRules::new(relative.to_path_buf());
+        for name in names {
+            let file: VirtualPath<FileMarker> = VirtualPath {
+                base: dir.base.clone(),
+                path: dir.path.join(name),
+                _phantom: PhantomData,
+            };
+            match fs::read_to_string(file.to_path_buf()) {
+                Ok(contents) => rules.add(&contents),
+                Err(e) if e.kind() == ErrorKind::NotFound => (),
+                Err(e) => errors.push(Pending::Error(Error::io("read ignore file", &file, e))),
+            }
+        }
+        IgnoreStack::push(parent, rules)
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool, ignores: Option<&IgnoreStack>) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if is_dir && self.git_ignore && relative.file_name() == Some(OsStr::new(".git")) {
+            return true;
+        }
+        self.ignore_globs.iter().any(|glob| glob.is_match(relative))
+            || ignores.is_some_and(|ignores| ignores.is_ignored(relative, is_dir))
+    }
+
+    /// Reads the entries of a directory that are not ignored, in reverse order of their names.
+    ///
+    /// Entries that cannot be inspected and ignore files that cannot be read are returned as
+    /// [`Pending::Error`].
+    pub(crate) fn read_entries(&self, pending: PendingDir) -> Result<Vec<Pending>> {
+        let PendingDir {
+            dir,
+            ignores,
+            ancestors,
+        } = pending;
+        let read = |e| Error::io("read directory", &dir, e);
+        let ancestors = match ancestors {
+            None if self.follow_links => Some(Arc::new(Ancestor {
+                id: dir_id(&dir.to_path_buf()).map_err(read)?,
+                parent: None,
+            })),
+            ancestors => ancestors,
+        };
+        let mut entries = Vec::new();
+        let ignores = self.read_ignore_files(&dir, ignores, &mut entries);
+
+        for entry in fs::read_dir(dir.to_path_buf()).map_err(read)? {
+            let entry = entry.map_err(read)?;
+            let (base, path) = (dir.base.clone(), dir.path.join(entry.file_name()));
+            let file_type = match entry.file_type() {
+                Ok(file_type) if file_type.is_symlink() && self.follow_links => {
+                    fs::metadata(entry.path())
+                        .map(|metadata| metadata.file_type())
+                        .map_err(|e| ("follow link", e))
+                }
+                Ok(file_type) => Ok(file_type),
+                Err(e) => Err(("read file type of", e)),
+            };
+            let entry_error = |base, path, op, e| {
+                let path: VirtualPath<AnyMarker> = VirtualPath {
+                    base,
+                    path,
+                    _phantom: PhantomData,
+                };
+                Pending::Error(Error::io(op, &path, e))
+            };
+            let file_type = match file_type {
+                Ok(file_type) => file_type,
+                Err((op, e)) => {
+                    entries.push(entry_error(base, path, op, e));
+                    continue;
+                }
+            };
+
+            if self.is_ignored(&path, file_type.is_dir(), ignores.as_deref()) {
+                continue;
+            }
+            entries.push(if file_type.is_dir() {
+                let ancestors = match &ancestors {
+                    Some(parent) => match dir_id(&entry.path()) {
+                        Ok(id) if Ancestor::contains(Some(parent), &id) => {
+                            let e =
+                                io::Error::other("link points at one of its parent directories");
+                            entries.push(entry_error(base, path, "follow link", e));
+                            continue;
+                        }
+                        Ok(id) => Some(Arc::new(Ancestor {
+                            id,
+                            parent: Some(parent.clone()),
+                        })),
+                        Err(e) => {
+                            entries.push(entry_error(base, path, "follow link", e));
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                Pending::Dir(PendingDir {
+                    dir: VirtualPath {
+                        base,
+                        path,
+                        _phantom: PhantomData,
+                    },
+                    ignores: ignores.clone(),
+                    ancestors,
+                })
+            } else {
+                Pending::File(VirtualPath {
+                    base,
+                    path,
+                    _phantom: PhantomData,
+                })
+            });
+        }
+
+        entries.sort_by(|a, b| b.path().cmp(a.path()));
+        Ok(entries)
+    }
+}
+
+impl Pending {
+    fn path(&self) -> &Path {
+        match self {
+            Self::Dir(pending) => &pending.dir.path,
+            Self::File(file) => &file.path,
+            Self::Error(e) => e.virtual_path().unwrap_or(Path::new("")),
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<VirtualPath<FileMarker>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop()? {
+                Pending::File(file) => return Some(Ok(file)),
+                Pending::Error(e) => return Some(Err(e)),
+                Pending::Dir(pending) => match self.read_entries(pending) {
+                    Ok(entries) => self.stack.extend(entries),
+                    Err(e) => return Some(Err(e)),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_tree;
+
+    #[test]
+    fn walks_files_depth_first_in_name_order() {
+        let (dir, base) = temp_tree(&[
+            ("b.txt", ""),
+            ("a/z.txt", ""),
+            ("a/b/c.txt", ""),
+            ("c/d.txt", ""),
+            ("empty/", ""),
+        ]);
+
+        let files: Vec<_> = VirtualPath::default()
+            .with_base(&base)
+            .walk()
+            .map(|file| file.unwrap().to_string())
+            .collect();
+
+        let expected: Vec<_> = ["a/b/c.txt", "a/z.txt", "b.txt", "c/d.txt"]
+            .iter()
+            .map(|file| dir.path().join(file).display().to_string())
+            .collect();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn missing_directory_is_an_error() {
+        let (_dir, base) = temp_tree(&[]);
+        let missing = VirtualPath::default()
+            .with_dir_raw("missing")
+            .with_base(&base);
+
+        let err = missing.walk().next().unwrap().unwrap_err();
+        assert_eq!(err.virtual_path(), Some(std::path::Path::new("missing")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_are_files_when_not_followed() {
+        let (dir, base) = temp_tree(&[("real/a.txt", "")]);
+        std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("link")).unwrap();
+
+        let root = VirtualPath::default().with_base(&base);
+        let walk = |follow| -> Vec<_> {
+            root.walk()
+                .follow_links(follow)
+                .map(|file| file.unwrap().relative().as_path().display().to_string())
+                .collect()
+        };
+
+        assert_eq!(walk(true), vec!["link/a.txt", "real/a.txt"]);
+        assert_eq!(walk(false), vec!["link", "real/a.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_links_and_link_loops_are_reported_per_entry() {
+        let (dir, base) = temp_tree(&[("sub/a.txt", ""), ("z.txt", "")]);
+        std::os::unix::fs::symlink(dir.path().join("missing"), dir.path().join("broken")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("sub/loop")).unwrap();
+
+        let results: Vec<_> = VirtualPath::default()
+            .with_base(&base)
+            .walk()
+            .map(|file| match file {
+                Ok(file) => file.relative().as_path().display().to_string(),
+                Err(e) => format!("error: {}", e.virtual_path().unwrap().display()),
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec!["error: broken", "sub/a.txt", "error: sub/loop", "z.txt"]
+        );
+    }
+
+    #[test]
+    fn nested_ignore_files_apply_below_their_directory() {
+        let (_dir, base) = temp_tree(&[
+            (
+                ".gitignore",
+                "*.log
+target/
+",
+            ),
+            ("a.log", ""),
+            ("target/out.txt", ""),
+            (
+                "docs/.ignore",
+                "!keep.log
+drafts
+",
+            ),
+            ("docs/keep.log", ""),
+            ("docs/other.log", ""),
+            ("docs/drafts/wip.md", ""),
+            ("docs/index.md", ""),
+            (".git/HEAD", ""),
+        ]);
+
+        let files: Vec<_> = VirtualPath::default()
+            .with_base(&base)
+            .walk()
+            .git_ignore(true)
+            .ignore(true)
+            .map(|file| file.unwrap().relative().as_path().display().to_string())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ".gitignore",
+                "docs/.ignore",
+                "docs/index.md",
+                "docs/keep.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_rules_are_relative_to_the_walked_directory() {
+        let (_dir, base) = temp_tree(&[
+            ("site/build/a.html", ""),
+            ("site/src/build/b.html", ""),
+            ("site/.custom", "/build\n"),
+        ]);
+
+        let files: Vec<_> = VirtualPath::default()
+            .with_dir_raw("site")
+            .with_base(&base)
+            .walk()
+            .ignore_file_name(".custom")
+            .ignore_glob(Glob::new(".custom").unwrap())
+            .map(|file| file.unwrap().relative().as_path().display().to_string())
+            .collect();
+        assert_eq!(files, vec!["site/src/build/b.html"]);
+    }
+
+    #[test]
+    fn unreadable_ignore_file_is_reported_and_walk_continues() {
+        let (dir, base) = temp_tree(&[("docs/index.md", ""), ("z.txt", "")]);
+        fs::write(dir.path().join("docs/.ignore"), [0xff, 0xfe]).unwrap();
+
+        let results: Vec<_> = VirtualPath::default()
+            .with_base(&base)
+            .walk()
+            .ignore(true)
+            .map(|file| match file {
+                Ok(file) => file.relative().as_path().display().to_string(),
+                Err(e) => format!("error: {}", e.virtual_path().unwrap().display()),
+            })
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                "docs/.ignore",
+                "error: docs/.ignore",
+                "docs/index.md",
+                "z.txt"
+            ]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::{marker::PhantomData, path::PathBuf};
+
+use crate::absolute::AbsolutePath;
+use crate::dirname::DirnameRef;
+use crate::env::{Environment, SystemEnv};
+use crate::marker::DirMarker;
+use crate::virtualpath::VirtualPath;
+
+/// An XDG base directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BaseDir {
+    /// `$XDG_CONFIG_HOME`, defaulting to `$HOME/.config`
+    Config,
+    /// `$XDG_DATA_HOME`, defaulting to `$HOME/.local/share`
+    Data,
+    /// `$XDG_CACHE_HOME`, defaulting to `$HOME/.cache`
+    Cache,
+    /// `$XDG_STATE_HOME`, defaulting to `$HOME/.local/state`
+    State,
+    /// `$XDG_RUNTIME_DIR`, which has no default
+    Runtime,
+}
+
+impl BaseDir {
+    /// Returns the name of the environment variable for this directory.
+    pub fn var(self) -> &'static str {
+        match self {
+            Self::Config => "XDG_CONFIG_HOME",
+            Self::Data => "XDG_DATA_HOME",
+            Self::Cache => "XDG_CACHE_HOME",
+            Self::State => "XDG_STATE_HOME",
+            Self::Runtime => "XDG_RUNTIME_DIR",
+        }
+    }
+
+    /// Returns the default location relative to the home directory, if the specification
+    /// defines one.
+    pub fn home_fallback(self) -> Option<&'static str> {
+        match self {
+            Self::Config => Some(".config"),
+            Self::Data => Some(".local/share"),
+            Self::Cache => Some(".cache"),
+            Self::State => Some(".local/state"),
+            Self::Runtime => None,
+        }
+    }
+}
+
+/// An error that may occur while locating an XDG base directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XdgError {
+    /// The variable was unset and the home directory could not be determined
+    NoHomeDir(BaseDir),
+    /// The variable was unset and the directory has no default
+    Unset(BaseDir),
+}
+
+impl std::fmt::Display for XdgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoHomeDir(dir) => write!(
+                f,
+                "{} is not set and home directory could not be determined",
+                dir.var()
+            ),
+            Self::Unset(dir) => write!(f, "{} is not set to an absolute path", dir.var()),
+        }
+    }
+}
+
+impl std::error::Error for XdgError {}
+
+impl AbsolutePath {
+    /// Returns `$XDG_CONFIG_HOME` or its default.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_config_home() -> Result<Self, XdgError> {
+        Self::xdg_dir(BaseDir::Config)
+    }
+
+    /// Returns `$XDG_DATA_HOME` or its default.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_data_home() -> Result<Self, XdgError> {
+        Self::xdg_dir(BaseDir::Data)
+    }
+
+    /// Returns `$XDG_CACHE_HOME` or its default.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_cache_home() -> Result<Self, XdgError> {
+        Self::xdg_dir(BaseDir::Cache)
+    }
+
+    /// Returns `$XDG_STATE_HOME` or its default.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_state_home() -> Result<Self, XdgError> {
+        Self::xdg_dir(BaseDir::State)
+    }
+
+    /// Returns `$XDG_RUNTIME_DIR`.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_runtime_dir() -> Result<Self, XdgError> {
+        Self::xdg_dir(BaseDir::Runtime)
+    }
+
+    /// Returns the given XDG base directory using the environment of the current process.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_dir(dir: BaseDir) -> Result<Self, XdgError> {
+        Self::xdg_dir_with(dir, &SystemEnv)
+    }
+
+    /// Returns the given XDG base directory using the given environment.
+    ///
+    /// Per the specification, variables that are empty or contain a relative path are ignored.
+    ///
+    /// ```
+    /// use vpath::{AbsolutePath, BaseDir, EnvMap};
+    ///
+    /// let env = EnvMap::new().with_var("HOME", "/home/blog");
+    ///
+    /// let cache = AbsolutePath::xdg_dir_with(BaseDir::Cache, &env).unwrap();
+    /// assert_eq!(cache.as_path().to_str(), Some("/home/blog/.cache"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// An `Err` will be returned if the variable is unset and either the directory has no default
+    /// or the home directory could not be determined.
+    pub fn xdg_dir_with<E>(dir: BaseDir, env: &E) -> Result<Self, XdgError>
+    where
+        E: Environment + ?Sized,
+    {
+        if let Some(path) = env
+            .var(dir.var())
+            .and_then(|value| Self::try_from(PathBuf::from(value)).ok())
+        {
+            return Ok(path);
+        }
+
+        let fallback = dir.home_fallback().ok_or(XdgError::Unset(dir))?;
+        let home = env
+            .home_dir()
+            .and_then(|home| Self::try_from(home).ok())
+            .ok_or(XdgError::NoHomeDir(dir))?;
+
+        Ok(Self::from_relative_to(&home, fallback))
+    }
+}
+
+impl VirtualPath<DirMarker> {
+    /// Returns an empty virtual path based at the application directory within the given XDG
+    /// base directory, using the environment of the current process.
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_app<D>(dir: BaseDir, app: D) -> Result<Self, XdgError>
+    where
+        D: AsRef<DirnameRef>,
+    {
+        Self::xdg_app_with(dir, app, &SystemEnv)
+    }
+
+    /// Returns an empty virtual path based at the application directory within the given XDG
+    /// base directory, using the given environment.
+    ///
+    /// ```
+    /// # use std::path::PathBuf;
+    /// use vpath::{BaseDir, Dirname, EnvMap, VirtualPath};
+    ///
+    /// let env = EnvMap::new().with_var("XDG_CACHE_HOME", "/var/cache");
+    ///
+    /// let cache = VirtualPath::xdg_app_with(BaseDir::Cache, Dirname::try_from("myapp").unwrap(), &env)
+    ///     .unwrap()
+    ///     .with_file_raw("index.bin");
+    /// assert_eq!(cache.to_path_buf(), PathBuf::from("/var/cache/myapp/index.bin"));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// See [`AbsolutePath::xdg_dir_with`].
+    pub fn xdg_app_with<D, E>(dir: BaseDir, app: D, env: &E) -> Result<Self, XdgError>
+    where
+        D: AsRef<DirnameRef>,
+        E: Environment + ?Sized,
+    {
+        let base = AbsolutePath::xdg_dir_with(dir, env)?.join(app);
+        Ok(VirtualPath {
+            base: base.0,
+            path: PathBuf::default(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::dirname::Dirname;
+    use crate::env::EnvMap;
+
+    fn home() -> EnvMap {
+        EnvMap::new().with_var("HOME", "/home/blog")
+    }
+
+    #[test]
+    fn uses_defaults_relative_to_home() {
+        let env = home();
+        let dir = |dir| AbsolutePath::xdg_dir_with(dir, &env).unwrap();
+
+        assert_eq!(
+            dir(BaseDir::Config).as_path(),
+            Path::new("/home/blog/.config")
+        );
+        assert_eq!(
+            dir(BaseDir::Data).as_path(),
+            Path::new("/home/blog/.local/share")
+        );
+        assert_eq!(
+            dir(BaseDir::Cache).as_path(),
+            Path::new("/home/blog/.cache")
+        );
+        assert_eq!(
+            dir(BaseDir::State).as_path(),
+            Path::new("/home/blog/.local/state")
+        );
+    }
+
+    #[test]
+    fn prefers_variable_over_default() {
+        let env = home().with_var("XDG_CONFIG_HOME", "/etc/blog");
+        let dir = AbsolutePath::xdg_dir_with(BaseDir::Config, &env).unwrap();
+
+        assert_eq!(dir.as_path(), Path::new("/etc/blog"));
+    }
+
+    #[test]
+    fn ignores_empty_and_relative_variables() {
+        let env = home()
+            .with_var("XDG_CONFIG_HOME", "")
+            .with_var("XDG_DATA_HOME", "data");
+
+        let config = AbsolutePath::xdg_dir_with(BaseDir::Config, &env).unwrap();
+        let data = AbsolutePath::xdg_dir_with(BaseDir::Data, &env).unwrap();
+
+        assert_eq!(config.as_path(), Path::new("/home/blog/.config"));
+        assert_eq!(data.as_path(), Path::new("/home/blog/.local/share"));
+    }
+
+    #[test]
+    fn runtime_dir_has_no_default() {
+        let err = AbsolutePath::xdg_dir_with(BaseDir::Runtime, &home()).unwrap_err();
+        assert_eq!(err, XdgError::Unset(BaseDir::Runtime));
+
+        let env = home().with_var("XDG_RUNTIME_DIR", "/run/user/1000");
+        let dir = AbsolutePath::xdg_dir_with(BaseDir::Runtime, &env).unwrap();
+        assert_eq!(dir.as_path(), Path::new("/run/user/1000"));
+    }
+
+    #[test]
+    fn fails_without_home() {
+        let err = AbsolutePath::xdg_dir_with(BaseDir::Cache, &EnvMap::new()).unwrap_err();
+        assert_eq!(err, XdgError::NoHomeDir(BaseDir::Cache));
+    }
+
+    #[test]
+    fn app_dir_is_the_base() {
+        let app = Dirname::try_from("myapp").unwrap();
+        let path = VirtualPath::xdg_app_with(BaseDir::Data, app, &home()).unwrap();
+
+        assert!(path.has_base());
+        assert_eq!(
+            path.to_path_buf(),
+            PathBuf::from("/home/blog/.local/share/myapp")
+        );
+    }
+}